@@ -1,3 +1,4 @@
+import argparse
 import io
 import json
 import os
@@ -128,6 +129,20 @@ async def recent():
     return await return_png(await renderer.screenshot(html))
 
 
+@app.route("/recent_timeline", methods=["POST"])
+async def recent_timeline():
+    data = await request.get_json()
+    html = render_html(f"{TEMPLATE_PATH}/recent_timeline.hbs", data)
+    return await return_png(await renderer.screenshot(html))
+
+
+@app.route("/recent_breakdown", methods=["POST"])
+async def recent_breakdown():
+    data = await request.get_json()
+    html = render_html(f"{TEMPLATE_PATH}/recent_breakdown.hbs", data)
+    return await return_png(await renderer.screenshot(html))
+
+
 @app.route("/leaderboard", methods=["POST"])
 async def leaderboard():
     data = await request.get_json()
@@ -155,6 +170,14 @@ async def single_ship():
     return await return_png(await renderer.screenshot(html))
 
 
+@app.route("/health", methods=["GET"])
+async def health():
+    # the browser is launched in the background, not ready until it's connected
+    if renderer and renderer.browser and renderer.browser.is_connected():
+        return "ok"
+    return "launching", 503
+
+
 @app.before_serving
 async def startup():
     app.add_background_task(Renderer.launch)
@@ -258,10 +281,14 @@ class Renderer:
 
 
 renderer = None
-html_renderer = pystache.Renderer()
+# partials like `{{> class_tier}}` are looked up in the template folder
+html_renderer = pystache.Renderer(search_dirs=TEMPLATE_PATH, file_extension="hbs")
 
 if __name__ == "__main__":
     config = hypercorn.config.Config()
-    config.bind = ["localhost:3000"]
+    parser = argparse.ArgumentParser()
+    parser.add_argument("--port", type=int, default=3000)
+    args = parser.parse_args()
+    config.bind = [f"localhost:{args.port}"]
     config.loglevel = "WARNING"
     asyncio.run(hypercorn.asyncio.serve(app, config))
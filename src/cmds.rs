@@ -1,10 +1,12 @@
 pub mod clan;
 pub mod clan_top;
+pub mod export;
 pub mod general;
 pub mod owner;
 pub mod patreon;
 pub mod recent;
 pub mod server_top;
+pub mod session;
 pub mod setting;
 pub mod tools;
 pub mod top;
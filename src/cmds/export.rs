@@ -0,0 +1,300 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use itertools::Itertools;
+use poise::{
+    CreateReply,
+    serenity_prelude::{CreateAttachment, User},
+};
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+use crate::{
+    Context, Error,
+    dc_utils::{ContextAddon, UserAddon, autocomplete},
+    structs::{
+        AutocompletePlayer, Mode, PlayerSnapshots, RetentionPolicy, ShipStats, ShipStatsCollection,
+        ShipTier, ShipsPara,
+    },
+    utils::{IsacError, IsacHelp, IsacInfo, wws_api::WowsApi},
+};
+
+/// discord's upload limit for non-boosted servers
+const EXPORT_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, poise::ChoiceParameter, Clone, Copy, Default)]
+pub enum ExportFormat {
+    #[default]
+    #[name = "csv"]
+    Csv,
+    #[name = "json"]
+    Json,
+}
+
+/// one ship in one mode of one snapshot
+#[derive(Serialize)]
+struct ExportRow {
+    timestamp: u64,
+    ship_id: u64,
+    ship_name: String,
+    tier: Option<ShipTier>,
+    mode: &'static str,
+    #[serde(flatten)]
+    stats: ShipStats,
+}
+
+/// Export your recent history as a CSV / JSON file
+#[poise::command(slash_command, user_cooldown = 10)]
+pub async fn export(
+    ctx: Context<'_>,
+    #[description = "file format, default: csv"] format: Option<ExportFormat>,
+    #[description = "diff of the last X days, default: all history"] days: Option<u64>,
+    #[description = "until X days ago, with `days`, default: now"] to: Option<u64>,
+    #[description = "player's ign, default: yourself"]
+    #[autocomplete = "autocomplete::player"]
+    player: Option<AutocompletePlayer>,
+    #[description = "@ping / discord user's ID, default: yourself"]
+    #[rename = "user"]
+    discord_user: Option<String>,
+) -> Result<(), Error> {
+    let _typing = ctx.typing().await;
+    let api = WowsApi::new(&ctx);
+    let partial_player = if let Some(autocomplete_player) = player {
+        autocomplete_player.fetch_partial_player(&api).await?
+    } else {
+        let user = if let Some(discord_user_str) = discord_user {
+            User::convert_strict(
+                ctx.serenity_context(),
+                ctx.guild_id(),
+                None,
+                &discord_user_str,
+            )
+            .await
+            .unwrap_or_else(|_| ctx.author().clone())
+        } else {
+            ctx.author().clone()
+        };
+        user.get_player(&ctx).await?
+    };
+    let max_day =
+        RetentionPolicy::get().query_day(ctx.data().patron.read().check_user(&ctx.author().id));
+    let Some(player_data) = PlayerSnapshots::load(partial_player).await? else {
+        Err(IsacError::Info(IsacInfo::GeneralError {
+            msg: "This player wasn't in the database, please use `/recent` first".to_string(),
+        }))?
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let oldest = now.saturating_sub(max_day * 86400);
+
+    let (rows, msg) = if let Some(days) = days {
+        let to_day = to.unwrap_or(0);
+        if days == 0 || days > max_day {
+            Err(IsacError::Info(IsacInfo::NeedPremium {
+                msg: format!("**{days}** is illegal, min: **1** max: **{max_day}**"),
+            }))?
+        }
+        if to_day >= days {
+            Err(IsacError::Help(IsacHelp::InvalidDayRange {
+                from_day: days,
+                to_day,
+            }))?
+        }
+        let start = player_data.get_date(&(now - days * 86400)).await;
+        // 0 day ago is the current ships, same as `/recent`
+        let end = match to_day {
+            0 => Some((
+                now,
+                partial_player
+                    .all_ships(&api, player_data.tracks_extra_modes())
+                    .await?,
+            )),
+            _ => player_data.get_date(&(now - to_day * 86400)).await,
+        };
+        let Some((start_time, end_time, stats)) = start
+            .zip(end)
+            .filter(|((start_time, _), (end_time, _))| start_time < end_time)
+            .and_then(|((start_time, old_ships), (end_time, new_ships))| {
+                new_ships
+                    .compare(old_ships)
+                    .map(|stats| (start_time, end_time, stats))
+            })
+        else {
+            Err(IsacError::Info(IsacInfo::GeneralError {
+                msg: format!("No battles between **{days}** and **{to_day}** days ago"),
+            }))?
+        };
+        let msg = match to_day {
+            0 => format!("Diff since <t:{start_time}:f>"),
+            _ => format!("Diff from <t:{start_time}:f> to <t:{end_time}:f>"),
+        };
+        (to_rows(&ctx.data().ships.read(), end_time, &stats), msg)
+    } else {
+        let ships_para = ctx.data().ships.read();
+        let rows = player_data
+            .data
+            .range(oldest..)
+            .flat_map(|(timestamp, ships)| to_rows(&ships_para, *timestamp, ships))
+            .collect_vec();
+        (rows, "Full history".to_string())
+    };
+
+    let (bytes, ext) = match format.unwrap_or_default() {
+        ExportFormat::Csv => (to_csv(&rows).into_bytes(), "csv"),
+        ExportFormat::Json => (serde_json::to_vec(&rows)?, "json"),
+    };
+    if bytes.len() > EXPORT_SIZE_LIMIT {
+        Err(IsacError::Info(IsacInfo::GeneralError {
+            msg: "The file is too large, please try a shorter `days`".to_string(),
+        }))?
+    }
+    let file_name = format!(
+        "{}_{}.{ext}",
+        partial_player.region.lower(),
+        partial_player.uid
+    );
+    ctx.send(
+        CreateReply::default()
+            .content(format!("{msg}, {} rows", rows.len()))
+            .attachment(CreateAttachment::bytes(bytes, file_name))
+            .reply(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// flatten the ships into per-ship, per-mode rows
+fn to_rows(ships_para: &ShipsPara, timestamp: u64, ships: &ShipStatsCollection) -> Vec<ExportRow> {
+    ships
+        .0
+        .iter()
+        .sorted_by_key(|(ship_id, _)| ship_id.0)
+        .flat_map(|(ship_id, pair)| {
+            let ship = ships_para.get(ship_id);
+            Mode::iter().filter_map(move |mode| {
+                pair.0.get(&mode).map(|stats| ExportRow {
+                    timestamp,
+                    ship_id: ship_id.0,
+                    ship_name: ship.map(|s| s.name.clone()).unwrap_or_default(),
+                    tier: ship.map(|s| s.tier),
+                    mode: mode.api_name(),
+                    stats: stats.clone(),
+                })
+            })
+        })
+        .collect()
+}
+
+fn to_csv(rows: &[ExportRow]) -> String {
+    let mut csv = ["timestamp", "ship_id", "ship_name", "tier", "mode"]
+        .into_iter()
+        .chain(ShipStats::FIELDS)
+        .join(",");
+    csv.push('\n');
+    for row in rows {
+        let fields = [
+            row.timestamp.to_string(),
+            row.ship_id.to_string(),
+            csv_escape(&row.ship_name),
+            row.tier.map(|t| (t as u8).to_string()).unwrap_or_default(),
+            row.mode.to_string(),
+        ];
+        csv.push_str(
+            &fields
+                .into_iter()
+                .chain(
+                    row.stats
+                        .values()
+                        .map(|v| v.map(|v| v.to_string()).unwrap_or_default()),
+                )
+                .join(","),
+        );
+        csv.push('\n');
+    }
+    csv
+}
+
+/// quote the field if it contains comma, quote or newline
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{csv_escape, to_csv, to_rows};
+    use crate::structs::{Ship, ShipId, ShipStats, ShipStatsCollection, ShipTier, ShipsPara};
+
+    fn fixture() -> (ShipsPara, ShipStatsCollection) {
+        let ship = Ship {
+            ship_id: ShipId(1),
+            tier: ShipTier::VIII,
+            name: "Big, \"Bad\"\nShip".to_string(),
+            ..Default::default()
+        };
+        let ships_para = ShipsPara(HashMap::from([(ship.ship_id, ship)]));
+        let stats = r#"{"battles_count":2,"wins":1,"damage_dealt":3,"frags":4,"planes_killed":5,"original_exp":6,"art_agro":7,"scouting_damage":8,"shots_by_main":9,"hits_by_main":10,"survived":11}"#;
+        let ships = serde_json::from_str(&format!(
+            r#"{{"1":{{"pvp":{stats},"pvp_solo":{stats}}},"2":{{"pvp":{stats}}}}}"#
+        ))
+        .unwrap();
+        (ships_para, ships)
+    }
+
+    #[test]
+    fn rows_per_ship_per_mode() {
+        let (ships_para, ships) = fixture();
+        let rows = to_rows(&ships_para, 42, &ships);
+        let keys = rows
+            .iter()
+            .map(|row| (row.ship_id, row.mode))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, [(1, "pvp"), (1, "pvp_solo"), (2, "pvp")]);
+        assert!(rows.iter().all(|row| row.timestamp == 42));
+        // not in ships_para, exported without a name
+        assert_eq!(rows[2].ship_name, "");
+        assert_eq!(rows[2].tier, None);
+    }
+
+    #[test]
+    fn csv_header_and_fields_follow_ship_stats() {
+        let (ships_para, ships) = fixture();
+        let csv = to_csv(&to_rows(&ships_para, 42, &ships));
+        let (header, body) = csv.split_once('\n').unwrap();
+        let header = header.split(',').collect::<Vec<_>>();
+        assert_eq!(
+            header[..5],
+            ["timestamp", "ship_id", "ship_name", "tier", "mode"]
+        );
+        assert_eq!(header[5..], ShipStats::FIELDS);
+
+        let last = body.lines().last().unwrap().split(',').collect::<Vec<_>>();
+        assert_eq!(last.len(), header.len());
+        assert_eq!(last[..5], ["42", "2", "", "", "pvp"]);
+        // ships_spotted is missing in the old data, left empty
+        assert_eq!(
+            last[5..],
+            [
+                "2", "1", "3", "4", "5", "6", "7", "8", "9", "10", "11", "", "0", "0", "0"
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_quotes_special_names() {
+        assert_eq!(csv_escape("Yamato"), "Yamato");
+        assert_eq!(csv_escape("A, B"), "\"A, B\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_escape("two\nlines"), "\"two\nlines\"");
+
+        let (ships_para, ships) = fixture();
+        let csv = to_csv(&to_rows(&ships_para, 42, &ships));
+        assert!(csv.contains("42,1,\"Big, \"\"Bad\"\"\nShip\",8,pvp,"));
+    }
+}
@@ -2,6 +2,7 @@ use poise::CreateReply;
 use poise::serenity_prelude::CreateEmbed;
 
 use crate::dc_utils::EasyEmbed;
+use crate::utils::upstream_health::UPSTREAM_HEALTH;
 use crate::{Context, Error};
 
 /// The link for inviting ISAC
@@ -21,3 +22,35 @@ pub async fn help(ctx: Context<'_>) -> Result<(), Error> {
     ctx.reply(HELP_MSG).await?;
     Ok(())
 }
+
+/// The health of the services ISAC depends on, WG's API included
+#[poise::command(prefix_command, slash_command, discard_spare_arguments)]
+pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
+    let description = match UPSTREAM_HEALTH.checked_at() {
+        Some(checked_at) => format!("Last checked <t:{checked_at}:R>"),
+        None => "Not checked yet, try again later".to_string(),
+    };
+    let mut embed = CreateEmbed::default_isac()
+        .title("ISAC status")
+        .description(description);
+    for report in UPSTREAM_HEALTH.report() {
+        let mut value = match report.latency {
+            Some(latency) => format!(
+                "`{}ms`, {:.0}% ok recently",
+                latency.as_millis(),
+                report.uptime * 100.0
+            ),
+            None => "-".to_string(),
+        };
+        if let Some(err) = report.last_error {
+            value += &format!("\nlast error: `{err}`");
+        }
+        embed = embed.field(
+            format!("{} {}", report.health.emoji(), report.service),
+            value,
+            true,
+        );
+    }
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use crate::dc_utils::{Args, ContextAddon};
 use crate::utils::LoadSaveFromJson;
+use crate::utils::render_cache::RENDER_CACHE;
+use crate::utils::response_cache::RESPONSE_CACHE;
+use crate::utils::snapshot_store::{self, JsonStore, SqliteStore};
 use crate::utils::wws_api::WowsApi;
 use crate::{Context, Error};
 use itertools::Itertools;
@@ -41,6 +44,68 @@ pub async fn update_src(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+/// import the json recent_DB into the sqlite store, it's safe to run it multiple times
+#[poise::command(prefix_command, owners_only, hide_in_help)]
+pub async fn migrate_snapshots(ctx: Context<'_>) -> Result<(), Error> {
+    let _typing = ctx.typing().await;
+    let count = tokio::task::spawn_blocking(|| {
+        let sqlite = SqliteStore::open(&snapshot_store::sqlite_path())?;
+        snapshot_store::migrate_json_to_sqlite(&JsonStore::default(), &sqlite)
+    })
+    .await??;
+    ctx.reply(format!("{count} players migrated")).await?;
+    Ok(())
+}
+
+/// show the vortex response cache, `flush` to drop all the cached responses
+#[poise::command(prefix_command, owners_only, hide_in_help)]
+pub async fn response_cache(ctx: Context<'_>, flush: Option<String>) -> Result<(), Error> {
+    let flushed = flush.is_some_and(|arg| arg == "flush");
+    if flushed {
+        RESPONSE_CACHE.flush();
+    }
+    let table = RESPONSE_CACHE
+        .stats()
+        .into_iter()
+        .map(|stats| {
+            format!(
+                "{:<12} ttl {:>4}s | {:>5} cached | {:>3} in flight | {} hits / {} misses",
+                stats.name,
+                stats.ttl.as_secs(),
+                stats.entries,
+                stats.in_flight,
+                stats.hits,
+                stats.misses
+            )
+        })
+        .join("\n");
+    let title = if flushed { "Flushed\n" } else { "" };
+    ctx.reply(format!("{title}```\n{table}\n```")).await?;
+    Ok(())
+}
+
+/// show the render cache, `flush` to remove all the cached images, e.g. after editing the templates
+#[poise::command(prefix_command, owners_only, hide_in_help)]
+pub async fn render_cache(ctx: Context<'_>, flush: Option<String>) -> Result<(), Error> {
+    let flushed = flush.is_some_and(|arg| arg == "flush");
+    if flushed {
+        RENDER_CACHE.flush().await;
+    }
+    let stats = RENDER_CACHE.stats().await;
+    let title = if flushed { "Flushed\n" } else { "" };
+    ctx.reply(format!(
+        "{title}```\nttl {}s | {} cached | {:.1} / {} MB | {} hits / {} misses\n```",
+        stats.ttl.as_secs(),
+        stats.entries,
+        stats.bytes as f64 / 1024.0 / 1024.0,
+        stats.max_bytes / 1024 / 1024,
+        stats.hits,
+        stats.misses
+    ))
+    .await?;
+    Ok(())
+}
+
 #[poise::command(prefix_command, owners_only, hide_in_help)]
 pub async fn guilds(ctx: Context<'_>) -> Result<(), Error> {
     let _cache = ctx.cache();
@@ -1,12 +1,17 @@
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
+use chrono::DateTime;
+use futures::StreamExt;
 use itertools::Itertools;
 use poise::{
     CreateReply,
     serenity_prelude::{
-        ComponentInteractionDataKind, CreateActionRow, CreateAttachment, CreateInteractionResponse,
-        CreateInteractionResponseMessage, CreateSelectMenu, CreateSelectMenuOption,
-        EditAttachments, EditMessage, Message, User,
+        ButtonStyle, ComponentInteractionDataKind, CreateActionRow, CreateAttachment, CreateButton,
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateSelectMenu,
+        CreateSelectMenuOption, EditAttachments, EditMessage, Message, User,
     },
 };
 
@@ -14,17 +19,19 @@ use crate::{
     Context, Data, Error,
     dc_utils::{Args, ContextAddon, UserAddon, autocomplete},
     structs::{
-        AutocompletePlayer, Mode, PartialPlayer, Player, PlayerSnapshots, PlayerSnapshotsType,
-        Ship, ShipId, ShipModeStatsPair, ShipStatsCollection,
+        AutocompletePlayer, Mode, PartialPlayer, Player, PlayerSnapshots, RetentionPolicy, Ship,
+        ShipClass, ShipId, ShipModeStatsPair, ShipStatsAnomaly, ShipStatsCollection, ShipTier,
+        SnapshotsGuard, Statistic,
     },
     template_data::{
-        RecentTemplate, RecentTemplateDiv, RecentTemplateShip, Render, SingleShipTemplate,
+        OverallTemplateClass, OverallTemplateTier, RecentBreakdownTemplate, RecentTemplate,
+        RecentTimelineDay, RecentTimelineTemplate, Render, SingleShipTemplate,
     },
-    utils::{IsacError, IsacInfo, wws_api::WowsApi},
+    utils::{IsacError, IsacHelp, IsacInfo, wws_api::WowsApi},
 };
 
-const RECENT_LAST_REQUEST_LIMIT: u64 = 14;
-const RECENT_OMIT_LIMIT: usize = 50;
+/// list at most X anomalies in the message
+const ANOMALY_NOTE_LIMIT: usize = 5;
 
 pub fn recent_hybrid() -> poise::Command<Data, Error> {
     poise::Command {
@@ -50,6 +57,7 @@ pub async fn recent(
     #[autocomplete = "autocomplete::ship"]
     ship_name: Option<String>,
     #[description = "battle type, default: pvp"] battle_type: Option<Mode>,
+    #[description = "until X days ago, default: now"] to: Option<u64>,
 ) -> Result<(), Error> {
     let partial_player = if let Some(autocomplete_player) = player {
         autocomplete_player.save_user_search_history(&ctx).await;
@@ -87,6 +95,7 @@ pub async fn recent(
         partial_player,
         battle_type.unwrap_or_default(),
         days.unwrap_or(1),
+        to.unwrap_or(0),
         ship,
     )
     .await
@@ -96,7 +105,12 @@ pub async fn recent(
 pub async fn recent_prefix(ctx: Context<'_>, #[rest] mut args: Args) -> Result<(), Error> {
     let partial_player = args.parse_user(&ctx).await?;
     let mode = args.parse_mode().unwrap_or_default();
-    let day = args.parse_day().unwrap_or(1);
+    // `.recent 14 7` for the stats between 14 and 7 days ago
+    let (day, to_day) = match args.parse_day_range() {
+        Some((a, Some(b))) => (a.max(b), a.min(b)),
+        Some((a, None)) => (a, 0),
+        None => (1, 0),
+    };
     let ship = if !args.is_empty() {
         // specific ship
         Some(args.parse_ship(&ctx).await?)
@@ -104,7 +118,7 @@ pub async fn recent_prefix(ctx: Context<'_>, #[rest] mut args: Args) -> Result<(
         None
     };
 
-    func_recent(&ctx, partial_player, mode, day, ship).await
+    func_recent(&ctx, partial_player, mode, day, to_day, ship).await
 }
 
 async fn func_recent(
@@ -112,14 +126,13 @@ async fn func_recent(
     partial_player: PartialPlayer,
     mode: Mode,
     day: u64,
+    to_day: u64,
     specific_ship: Option<Ship>,
 ) -> Result<(), Error> {
     let typing1 = ctx.typing().await;
     let api = WowsApi::new(ctx);
-    let max_day = match ctx.data().patron.read().check_user(&ctx.author().id) {
-        true => 91_u64, // well its actually 90, this is for some 90 days data get ceiling to 91
-        false => 30_u64,
-    };
+    let max_day =
+        RetentionPolicy::get().query_day(ctx.data().patron.read().check_user(&ctx.author().id));
     let player = partial_player.full_player(&api).await?;
     let filter = specific_ship
         .as_ref()
@@ -132,8 +145,16 @@ async fn func_recent(
     //         Box::new(|_k, _v| true)
     //     };
 
-    let mut current_ships = partial_player.all_ships(&api).await?;
-    let (is_new, is_active, player_data) = load_player(&player, &current_ships).await;
+    // see `PlayerSnapshots::tracks_extra_modes`
+    let extra_modes = mode.is_extra()
+        || PlayerSnapshots::load_latest(partial_player)
+            .await?
+            .is_some_and(|ships| ships.has_extra_modes());
+    let mut current_ships = partial_player.all_ships(&api, extra_modes).await?;
+    let (is_new, is_active, player_data) = {
+        let guard = PlayerSnapshots::lock(player.partial_player).await;
+        load_player(&guard, &player, &current_ships).await?
+    };
     if let Some(f) = filter {
         current_ships.0.retain(f);
     }
@@ -149,7 +170,8 @@ async fn func_recent(
         .unwrap()
         .as_secs();
 
-    let mut target_day = day;
+    let days_ago = |time: u64| ((now - time) as f64 / 86400.0).ceil() as u64;
+    let mut target = (day, to_day);
     let mut ask_struct = AskDay::new(
         ctx,
         player.ign.clone(),
@@ -160,46 +182,79 @@ async fn func_recent(
 
     typing1.stop();
     // getting the stats diff compared with history
-    let (exact_day, stats) = loop {
-        if target_day > max_day {
+    let ((exact_day, exact_day_to), (start_time, end_time), stats, note) = loop {
+        let (from_day, to_day) = target;
+        if from_day > max_day {
             Err(IsacError::Info(IsacInfo::NeedPremium {
-                msg: format!("**{target_day}** is illegal, min: **1** max: **{max_day}**"),
+                msg: format!("**{from_day}** is illegal, min: **1** max: **{max_day}**"),
             }))?
         }
-        let target_time = now - target_day * 86400;
-        if let Some((exact_time, stats)) =
-            player_data
-                .get_date(&target_time)
-                .await
-                .and_then(|(exact_time, mut old_ships)| {
-                    if let Some(f) = filter {
-                        old_ships.0.retain(f);
-                    }
-                    current_ships
-                        .compare(old_ships)
-                        .map(|stats| (exact_time, stats))
-                })
+        if to_day >= from_day {
+            Err(IsacError::Help(IsacHelp::InvalidDayRange {
+                from_day,
+                to_day,
+            }))?
+        }
+        let from_time = now - from_day * 86400;
+        let to_time = now - to_day * 86400;
+        let start = player_data.get_date(&from_time).await;
+        // 0 day ago is the current ships
+        let end = match to_day {
+            0 => Some((now, current_ships.clone())),
+            _ => player_data.get_date(&to_time).await,
+        };
+        if let Some(((start_time, mut old_ships), (end_time, mut new_ships))) = start
+            .zip(end)
+            .filter(|((start_time, _), (end_time, _))| start_time < end_time)
         {
-            break (((now - exact_time) as f64 / 86400.0).ceil() as u64, stats);
-        } else {
-            // no data or the same, ask user to re-select
-            let available_times = player_data.available_dates(&target_time);
-            let available_days = available_times
-                .iter()
-                .map(|t| ((now - *t) as f64 / 86400.0).ceil() as u64)
-                .collect_vec();
-            // pick date here
-            if let Some(selected_day) = ask_struct.ask(target_day, available_days).await? {
-                // next iter
-                target_day = selected_day;
-            } else {
-                ask_struct.finished().await?;
-                Err(IsacError::Cancelled)?
+            if let Some(f) = filter {
+                old_ships.0.retain(f);
+                new_ships.0.retain(f);
+            }
+            let note = anomaly_note(ctx, &new_ships.anomalies(&old_ships, end_time - start_time));
+            if let Some(stats) = new_ships.compare(old_ships) {
+                let exact_day_to = (to_day != 0).then(|| days_ago(end_time));
+                break (
+                    (days_ago(start_time), exact_day_to),
+                    (start_time, end_time),
+                    stats,
+                    note,
+                );
             }
+            // might be 0 battle because of the anomalies, let the user know
+            ask_struct.note = note;
+        }
+        // no data or the same, ask user to re-select
+        let available_from = player_data
+            .available_dates(&from_time)
+            .into_iter()
+            .map(days_ago)
+            .collect_vec();
+        // only asking the end of the range if user gave one
+        let available_to = match to_day {
+            0 => vec![],
+            _ => std::iter::once(0)
+                .chain(
+                    player_data
+                        .data
+                        .keys()
+                        .filter(|&&date| date > from_time)
+                        .map(|&date| days_ago(date)),
+                )
+                .filter(|&day| day < from_day && day != to_day)
+                .collect_vec(),
+        };
+        // pick date here
+        if let Some(selected) = ask_struct.ask(target, available_from, available_to).await? {
+            // next iter
+            target = selected;
+        } else {
+            ask_struct.finished().await?;
+            Err(IsacError::Cancelled)?
         }
     };
     // got the history, constructing template data
-    let _typing2 = ctx.typing().await;
+    let typing2 = ctx.typing().await;
     // parsing and render
     let expected = &ctx.data().expected;
     let clan = player.clan(&api).await.ok();
@@ -207,6 +262,7 @@ async fn func_recent(
     let img = if let Some(ship) = specific_ship.as_ref() {
         // recent ship
         let ship_stats = stats
+            .clone()
             .get_ship(&ship.ship_id)
             .expect("it should not be None");
 
@@ -214,99 +270,290 @@ async fn func_recent(
             ctx,
             ship.clone(),
             None,
-            format!("({} days) {}", exact_day, mode.render_name()),
+            match exact_day_to {
+                Some(exact_day_to) => format!(
+                    "({exact_day} ~ {exact_day_to} days ago) {}",
+                    mode.render_name()
+                ),
+                None => format!("({} days) {}", exact_day, mode.render_name()),
+            },
             ship_stats,
             mode,
-            clan,
-            player,
+            clan.clone(),
+            player.clone(),
         )?;
         data.render(&ctx.data().client).await?
     } else {
         // recent all
-        let div = RecentTemplateDiv {
-            pvp: stats.to_statistic(expected, Mode::Pvp),
-            pvp_solo: stats.to_statistic(expected, Mode::Solo),
-            pvp_div2: stats.to_statistic(expected, Mode::Div2),
-            pvp_div3: stats.to_statistic(expected, Mode::Div3),
-            rank_solo: stats.to_statistic(expected, Mode::Rank),
-        };
-        let ships = stats
-            .0
-            .into_iter()
-            .filter_map(|(ship_id, ship_stats)| {
-                ship_stats
-                    .to_statistic(&ship_id, expected, mode)
-                    .map(|stats| RecentTemplateShip {
-                        info: ship_id.get_ship(&ctx.data().ships).unwrap_or_default(),
-                        stats,
-                    })
-            })
-            .sorted_by_key(|ship| (-(ship.stats.battles as i64), -(ship.info.tier as i64)))
-            .take(RECENT_OMIT_LIMIT)
-            .collect_vec();
-
         let data = RecentTemplate {
-            clan,
-            user: player,
-            ships,
             day: exact_day,
-            suffix: mode.render_name().to_string(),
-            main: div.get_mode(&mode).cloned().unwrap_or_default(),
-            div,
+            day_to: exact_day_to,
+            ..RecentTemplate::new(ctx, &stats, mode, clan.clone(), player.clone())
         };
         data.render(&ctx.data().client).await?
     };
 
+    // class / tier breakdown is meaningless for a single ship
+    let mut view = RecentView::new(specific_ship.is_none());
     let att = CreateAttachment::bytes(img, "image.png");
-    if let Some(mut msg) = ask_struct.ask_msg {
-        msg.edit(
-            ctx,
-            EditMessage::default()
-                .components(vec![])
-                .attachments(EditAttachments::new().add(att)),
-        )
-        .await?
+    let mut msg = if let Some(mut msg) = ask_struct.ask_msg {
+        let mut edit = EditMessage::default()
+            .components(view.build())
+            .attachments(EditAttachments::new().add(att));
+        if let Some(note) = note {
+            edit = edit.content(note);
+        }
+        msg.edit(ctx, edit).await?;
+        msg
     } else {
-        let _msg = ctx
-            .send(CreateReply::default().attachment(att).reply(true))
-            .await?;
-    }
+        let mut reply = CreateReply::default()
+            .attachment(att)
+            .components(view.build())
+            .reply(true);
+        if let Some(note) = note {
+            reply = reply.content(note);
+        }
+        ctx.send(reply).await?.into_message().await?
+    };
+    typing2.stop();
 
+    // waiting for interactions
+    while let Some(interaction) = msg
+        .await_component_interactions(ctx)
+        .timeout(Duration::from_secs(60))
+        .author_id(ctx.author().id)
+        .stream()
+        .next()
+        .await
+    {
+        let _typing = ctx.typing().await;
+        match interaction.data.custom_id.as_str() {
+            "recent_timeline" => {
+                // disable button first
+                view.timeline_btn_disabled = true;
+                let _ok = interaction
+                    .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                    .await;
+                // the end of the range is the current ships if it's now
+                let end_ships = match exact_day_to {
+                    Some(_) => player_data.data.get(&end_time).cloned().unwrap_or_default(),
+                    None => current_ships.clone(),
+                };
+                let days = player_data
+                    .timeline(start_time, (end_time, &end_ships))
+                    .into_iter()
+                    .map(|(date, ships)| RecentTimelineDay {
+                        date: DateTime::from_timestamp(date as i64, 0)
+                            .map(|date| date.format("%m/%d").to_string())
+                            .unwrap_or_default(),
+                        stats: ships.and_then(|mut ships| {
+                            if let Some(f) = filter {
+                                ships.0.retain(f);
+                            }
+                            ships.to_statistic(expected, mode)
+                        }),
+                    })
+                    .collect_vec();
+                let data = RecentTimelineTemplate {
+                    clan: clan.clone(),
+                    user: player.clone(),
+                    day: exact_day,
+                    day_to: exact_day_to,
+                    suffix: match specific_ship.as_ref() {
+                        Some(ship) => format!("{} {}", ship.name, mode.render_name()),
+                        None => mode.render_name().to_string(),
+                    },
+                    days,
+                };
+                let img_timeline = data.render(&ctx.data().client).await?;
+                let _ok = msg
+                    .edit(
+                        ctx,
+                        EditMessage::new()
+                            .attachments(
+                                EditAttachments::keep_all(&msg).add(CreateAttachment::bytes(
+                                    img_timeline,
+                                    "image_timeline.png",
+                                )),
+                            )
+                            .components(view.build()),
+                    )
+                    .await;
+            }
+            "recent_breakdown" => {
+                // disable button first
+                view.breakdown_btn_disabled = true;
+                let _ok = interaction
+                    .create_response(ctx, CreateInteractionResponse::Acknowledge)
+                    .await;
+                let class: OverallTemplateClass = stats
+                    .clone()
+                    .sort_class(ctx)
+                    .await
+                    .into_iter()
+                    .map(|(class, ships)| {
+                        (
+                            class,
+                            ships.to_statistic(expected, mode).unwrap_or_default(),
+                        )
+                    })
+                    .collect::<HashMap<ShipClass, Statistic>>()
+                    .into();
+                let tier: OverallTemplateTier = stats
+                    .clone()
+                    .sort_tier(ctx)
+                    .await
+                    .into_iter()
+                    .map(|(tier, ships)| {
+                        (tier, ships.to_statistic(expected, mode).unwrap_or_default())
+                    })
+                    .collect::<HashMap<ShipTier, Statistic>>()
+                    .into();
+                let data = RecentBreakdownTemplate {
+                    clan: clan.clone(),
+                    user: player.clone(),
+                    day: exact_day,
+                    day_to: exact_day_to,
+                    suffix: mode.render_name().to_string(),
+                    main: stats.to_statistic(expected, mode).unwrap_or_default(),
+                    class,
+                    tier,
+                };
+                let img_breakdown = data.render(&ctx.data().client).await?;
+                let _ok = msg
+                    .edit(
+                        ctx,
+                        EditMessage::new()
+                            .attachments(EditAttachments::keep_all(&msg).add(
+                                CreateAttachment::bytes(img_breakdown, "image_breakdown.png"),
+                            ))
+                            .components(view.build()),
+                    )
+                    .await;
+            }
+            _ => {}
+        }
+    }
+    // timeout;
+    msg.edit(ctx, EditMessage::new().components(view.timeout().build()))
+        .await?;
     Ok(())
 }
+
+struct RecentView {
+    has_breakdown: bool,
+    timeline_btn_disabled: bool,
+    breakdown_btn_disabled: bool,
+}
+
+impl RecentView {
+    fn new(has_breakdown: bool) -> Self {
+        Self {
+            has_breakdown,
+            timeline_btn_disabled: false,
+            breakdown_btn_disabled: false,
+        }
+    }
+
+    fn build(&self) -> Vec<CreateActionRow> {
+        let mut buttons = vec![
+            CreateButton::new("recent_timeline")
+                .style(ButtonStyle::Secondary)
+                .label("timeline")
+                .disabled(self.timeline_btn_disabled),
+        ];
+        if self.has_breakdown {
+            buttons.push(
+                CreateButton::new("recent_breakdown")
+                    .style(ButtonStyle::Secondary)
+                    .label("stats by class & tier")
+                    .disabled(self.breakdown_btn_disabled),
+            );
+        }
+        vec![CreateActionRow::Buttons(buttons)]
+    }
+
+    fn timeout(&mut self) -> &Self {
+        self.timeline_btn_disabled = true;
+        self.breakdown_btn_disabled = true;
+        self
+    }
+}
+/// describe the anomalies for user, None if there's no anomaly
+fn anomaly_note(ctx: &Context<'_>, anomalies: &[ShipStatsAnomaly]) -> Option<String> {
+    if anomalies.is_empty() {
+        return None;
+    }
+    let ship_name = |ship_id: &ShipId| {
+        ship_id
+            .get_ship(&ctx.data().ships)
+            .map_or_else(|| ship_id.0.to_string(), |ship| ship.name)
+    };
+    let mut note = "⚠️ Abnormal stats found (account rollback, stats reset or merged?), the result might be incomplete:".to_string();
+    for anomaly in anomalies.iter().take(ANOMALY_NOTE_LIMIT) {
+        let line = match anomaly {
+            ShipStatsAnomaly::Decreased { ship_id, mode } => {
+                format!(
+                    "**{}** {} stats decreased",
+                    ship_name(ship_id),
+                    mode.upper()
+                )
+            }
+            ShipStatsAnomaly::Disappeared { ship_id } => {
+                format!("**{}** disappeared", ship_name(ship_id))
+            }
+            ShipStatsAnomaly::ImpossibleJump {
+                ship_id,
+                mode,
+                battles,
+            } => format!(
+                "**{}** {battles} {} battles in too short time",
+                ship_name(ship_id),
+                mode.upper()
+            ),
+        };
+        note.push_str(&format!("\n- {line}"));
+    }
+    if anomalies.len() > ANOMALY_NOTE_LIMIT {
+        note.push_str(&format!(
+            "\n- and {} more",
+            anomalies.len() - ANOMALY_NOTE_LIMIT
+        ));
+    }
+    Some(note)
+}
+
 /// load player data, update the last_requst timestamp, put in current_ships if needed
-async fn load_player(
+///
+/// the caller holds the player's [`PlayerSnapshots::lock`] until it's done with saving
+pub async fn load_player(
+    _guard: &SnapshotsGuard,
     player: &Player,
     curren_ships: &ShipStatsCollection,
     // QA 打包成一個struct會比較好嗎? (下面PlayerSnapshotsLoadResult)， 但還是得攤開，而且攤開可以利用unused強迫處理
-) -> (bool, bool, PlayerSnapshots) {
+) -> Result<(bool, bool, PlayerSnapshots), IsacError> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
 
     let (is_new, mut player_data) =
-        if let Some(player_data) = PlayerSnapshots::load(player.partial_player).await {
+        if let Some(player_data) = PlayerSnapshots::load(player.partial_player).await? {
             (false, player_data)
         } else {
             let player_data = PlayerSnapshots::init(player.partial_player).await;
             (true, player_data)
         };
-    let is_active = match player_data.last_request {
-        PlayerSnapshotsType::Premium => true,
-        PlayerSnapshotsType::Normal(timestamp) => {
-            now - timestamp < RECENT_LAST_REQUEST_LIMIT * 86400
-        }
-    };
+    let is_active = player_data.is_active(now);
     // update the last_requst timestamp
     player_data.update_last_request(player.premium);
     // put in current_ships if needed
     if now - player_data.last_update_at > 86400 {
         player_data.insert(curren_ships.clone());
     }
-    player_data.save().await;
+    player_data.save().await?;
 
-    (is_new, is_active, player_data)
+    Ok((is_new, is_active, player_data))
 }
 
 // pub struct PlayerSnapshotsLoadResult {
@@ -317,6 +564,7 @@ async fn load_player(
 
 pub struct AskDay<'a> {
     pub ctx: &'a Context<'a>,
+    pub note: Option<String>, // e.g. the anomalies
     pub ign: String,
     pub mode: Mode,
     pub ask_msg: Option<Message>,
@@ -337,57 +585,81 @@ impl<'a> AskDay<'a> {
             ign,
             mode,
             ask_msg: None,
+            note: None,
             is_active,
             ship,
         }
     }
-    /// ask user to select a day, return None if user didn't response
+    /// ask user to select the days, return None if user didn't response
+    ///
+    /// `current` and the returned one are `(from, to)`, `to` is 0 for now
     pub async fn ask(
         &mut self,
-        current_day: u64,
-        available_days: Vec<u64>,
-    ) -> Result<Option<u64>, Error> {
+        current: (u64, u64),
+        available_from: Vec<u64>,
+        available_to: Vec<u64>,
+    ) -> Result<Option<(u64, u64)>, Error> {
         // setting stuffs up
-        // QA 原本單純用view.is_some()判斷，但過程view會被moved，才加了這個 has_choices，有更好的做法?
-        let (has_choices, view) = {
-            if available_days.is_empty() {
-                (false, vec![])
-            } else {
-                let options = available_days
-                    .iter()
-                    .sorted()
-                    .dedup()
-                    .take(25)
-                    .map(|day| CreateSelectMenuOption::new(day.to_string(), day.to_string()))
-                    .collect_vec();
-                let view = vec![CreateActionRow::SelectMenu(
-                    CreateSelectMenu::new(
-                        "recent_select",
-                        poise::serenity_prelude::CreateSelectMenuKind::String { options },
-                    )
-                    .min_values(1)
-                    .max_values(1),
-                )];
-
-                (true, view)
-            }
+        let select_menu = |custom_id: &str, placeholder: &str, days: Vec<u64>| {
+            let options = days
+                .into_iter()
+                .sorted()
+                .dedup()
+                .take(25)
+                .map(|day| match day {
+                    0 => CreateSelectMenuOption::new("now", "0"),
+                    _ => CreateSelectMenuOption::new(day.to_string(), day.to_string()),
+                })
+                .collect_vec();
+            CreateActionRow::SelectMenu(
+                CreateSelectMenu::new(
+                    custom_id,
+                    poise::serenity_prelude::CreateSelectMenuKind::String { options },
+                )
+                .placeholder(placeholder)
+                .min_values(1)
+                .max_values(1),
+            )
         };
+        let mut view = vec![];
+        if !available_from.is_empty() {
+            view.push(select_menu(
+                "recent_select",
+                "from X days ago",
+                available_from,
+            ));
+        }
+        if !available_to.is_empty() {
+            view.push(select_menu(
+                "recent_select_to",
+                "to X days ago",
+                available_to,
+            ));
+        }
+        let has_choices = !view.is_empty();
+
         let msg_content = {
             let specific_ship = self
                 .ship
                 .as_ref()
                 .map_or_else(|| "".to_string(), |ship| format!(" in **{}**", ship.name));
+            let period = match current {
+                (from, 0) => format!("last **{from}** day"),
+                (from, to) => format!("between **{from}** and **{to}** days ago"),
+            };
             let mut content = format!(
-                "`{}` played 0 battle{specific_ship} in **{}** last **{}** day.",
+                "`{}` played 0 battle{specific_ship} in **{}** {period}.",
                 self.ign,
                 self.mode.upper(),
-                current_day
             );
+            if let Some(note) = self.note.as_ref() {
+                content.push_str(&format!("\n{note}"));
+            }
             if has_choices {
                 content.push_str("\nChoose an older date?");
             }
             if !self.is_active {
-                content.push_str("```\nYou didn't use this command in the last 14 days, so ISAC stopped tracking your account, plz play a game and try it again```");
+                content.push_str(&format!("```\nYou didn't use this command in the last {} days, so ISAC stopped tracking your account, plz play a game and try it again```", RetentionPolicy::get().inactive_days));
             }
             content
         };
@@ -396,9 +668,7 @@ impl<'a> AskDay<'a> {
             // edit
             self_ask_msg
                 .edit(self.ctx, {
-                    EditMessage::default()
-                        .content(msg_content)
-                        .components(if has_choices { view } else { vec![] })
+                    EditMessage::default().content(msg_content).components(view)
                 })
                 .await?;
         } else {
@@ -409,7 +679,7 @@ impl<'a> AskDay<'a> {
                     CreateReply::default()
                         .content(msg_content)
                         .reply(true)
-                        .components(if has_choices { view } else { vec![] })
+                        .components(view)
                 })
                 .await?
                 .into_message()
@@ -437,11 +707,15 @@ impl<'a> AskDay<'a> {
                     ),
                 )
                 .await;
-            Some(match &interaction.data.kind {
+            let selected = match &interaction.data.kind {
                 ComponentInteractionDataKind::StringSelect { values } => values[0]
                     .parse::<u64>()
                     .expect("it was the day number we provided"),
                 _ => unreachable!(),
+            };
+            Some(match interaction.data.custom_id.as_str() {
+                "recent_select_to" => (current.0, selected),
+                _ => (selected, current.1),
             })
         } else {
             None
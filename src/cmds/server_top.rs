@@ -1,6 +1,9 @@
-use futures::{StreamExt, future::join_all};
+use std::collections::HashMap;
+
+use futures::{StreamExt, future::join_all, join};
 use itertools::Itertools;
 use poise::{CreateReply, serenity_prelude::CreateAttachment};
+use tracing::warn;
 
 use crate::{
     Context, Data, Error,
@@ -72,14 +75,20 @@ async fn func_server_top(ctx: Context<'_>, ship: Ship) -> Result<(), Error> {
     // let mut players = HashMap::new();
 
     let stream = futures::stream::iter(p_players.into_iter().map(|p_player| async move {
-        let record = match PlayerSnapshots::load(p_player).await {
-            Some(snapshots) => Some(snapshots.latest_snapshot().expect("shouldn't be None")),
+        let record = match PlayerSnapshots::load_latest(p_player).await {
+            Ok(Some(latest)) => Some(latest),
+            // logged already, skip him instead of overwriting his data
+            Err(_) => None,
             // no snapshot, fetch and save
-            None => {
-                if let Ok(current_ships) = p_player.all_ships(api_ref).await {
-                    let mut snapshots = PlayerSnapshots::init(p_player).await;
-                    snapshots.insert(current_ships.clone());
-                    snapshots.save().await;
+            Ok(None) => {
+                if let Ok(current_ships) = p_player.all_ships(api_ref, false).await {
+                    let _guard = PlayerSnapshots::lock(p_player).await;
+                    // someone might have saved him while fetching
+                    if let Ok(None) = PlayerSnapshots::load_latest(p_player).await {
+                        let mut snapshots = PlayerSnapshots::init(p_player).await;
+                        snapshots.insert(current_ships.clone());
+                        let _ = snapshots.save().await;
+                    }
                     Some(current_ships)
                 } else {
                     // maybe hidden profile or sth
@@ -130,24 +139,45 @@ async fn func_server_top(ctx: Context<'_>, ship: Ship) -> Result<(), Error> {
     };
     lb_players.truncate(truncate_len);
 
-    // turn PartialPlayer to Player
-    let mut lb_players = join_all(lb_players.into_iter().map(|(i, p, s)| async move {
-        // if he's cached but hidden after that
-        let p = p.full_player(api_ref).await.unwrap_or_default();
-        let clan_tag = p
-            .clan(&api)
-            .await
-            .map(|c| c.tag.with_brackets())
+    // turn PartialPlayer to Player, in batches instead of one request per player
+    let partial_players = lb_players.iter().map(|(_, p, _)| *p).collect::<Vec<_>>();
+    let (players, clan_tags) = join!(
+        api.full_players(&partial_players),
+        api.player_clan_tags(&partial_players)
+    );
+    // the batch has no dogtag and private karma, fine since `server_top.hbs` shows the ign only
+    // clan tags are cosmetic, render without them rather than failing
+    let clan_tags = clan_tags.unwrap_or_default();
+    let mut players = players.unwrap_or_else(|err| {
+        warn!("server_top: batch lookup failed, falling back per player: {err:?}");
+        HashMap::new()
+    });
+    let api_ref = &api;
+    let mut lb_players = join_all(lb_players.into_iter().map(|(i, p, s)| {
+        let player = players.remove(&p);
+        let clan = clan_tags
+            .get(&p)
+            .map(|tag| tag.with_brackets())
             .unwrap_or_default();
-        ServerTopPlayer {
-            color: "#fff".to_string(),
-            rank: i,
-            clan: clan_tag,
-            player: p,
-            stats: s,
+        async move {
+            // missing from the batch, ask again one by one, and leave it out if that fails too
+            let player = match player {
+                Some(player) => player,
+                None => p.full_player(api_ref).await.ok()?,
+            };
+            Some(ServerTopPlayer {
+                color: "#fff".to_string(),
+                rank: i,
+                clan,
+                player,
+                stats: s,
+            })
         }
     }))
-    .await;
+    .await
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
 
     // color patrons and author
     {
@@ -176,9 +206,8 @@ async fn func_server_top(ctx: Context<'_>, ship: Ship) -> Result<(), Error> {
                 .reply(true),
         )
         .await?;
-    println!(
-        "server members: {}, took time: {:.4}s",
-        p_players_len,
+    tracing::debug!(
+        "server_top: {p_players_len} members, took {:.4}s",
         time.elapsed().as_secs_f32()
     );
     Ok(())
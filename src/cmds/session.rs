@@ -0,0 +1,106 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use poise::{CreateReply, serenity_prelude::CreateAttachment};
+
+use crate::{
+    Context, Error,
+    cmds::recent::load_player,
+    dc_utils::{ContextAddon, UserAddon},
+    structs::{Mode, PlayerSnapshots},
+    template_data::{RecentTemplate, Render},
+    utils::{IsacError, IsacInfo, wws_api::WowsApi},
+};
+
+/// Track your stats between a start and an end
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommands("start", "end"),
+    subcommand_required
+)]
+pub async fn session(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Start a session, `/session end` to see the stats
+#[poise::command(slash_command, prefix_command, user_cooldown = 3)]
+pub async fn start(ctx: Context<'_>) -> Result<(), Error> {
+    let _typing = ctx.typing().await;
+    let api = WowsApi::new(&ctx);
+    let partial_player = ctx.author().get_player(&ctx).await?;
+    let player = partial_player.full_player(&api).await?;
+    // the mode is picked at `/session end`, so all of them
+    let current_ships = partial_player.all_ships(&api, true).await?;
+
+    let guard = PlayerSnapshots::lock(partial_player).await;
+    let (_is_new, _is_active, mut player_data) =
+        load_player(&guard, &player, &current_ships).await?;
+    let restarted = player_data.session.is_some();
+    player_data.session = Some((now(), current_ships));
+    player_data.save().await?;
+
+    let msg = match restarted {
+        true => format!("Session restarted for `{}`", player.ign),
+        false => format!("Session started for `{}`", player.ign),
+    };
+    ctx.reply(format!("{msg}, use `/session end` after your games"))
+        .await?;
+    Ok(())
+}
+
+/// End the session and show the stats since `/session start`
+#[poise::command(slash_command, prefix_command, user_cooldown = 3)]
+pub async fn end(
+    ctx: Context<'_>,
+    #[description = "battle type, default: pvp"] battle_type: Option<Mode>,
+) -> Result<(), Error> {
+    let _typing = ctx.typing().await;
+    let mode = battle_type.unwrap_or_default();
+    let api = WowsApi::new(&ctx);
+    let partial_player = ctx.author().get_player(&ctx).await?;
+    let Some((started_at, session_ships)) = PlayerSnapshots::load(partial_player)
+        .await?
+        .and_then(|player_data| player_data.session)
+    else {
+        Err(IsacError::Info(IsacInfo::GeneralError {
+            msg: "No session found, use `/session start` first".to_string(),
+        }))?
+    };
+    let player = partial_player.full_player(&api).await?;
+    let current_ships = partial_player.all_ships(&api, true).await?;
+    // the session is over either way
+    {
+        let guard = PlayerSnapshots::lock(partial_player).await;
+        let (_is_new, _is_active, mut player_data) =
+            load_player(&guard, &player, &current_ships).await?;
+        player_data.session = None;
+        player_data.save().await?;
+    }
+
+    let Some(stats) = current_ships.compare(session_ships) else {
+        Err(IsacError::Info(IsacInfo::GeneralError {
+            msg: format!("`{}` played 0 battle in this session", player.ign),
+        }))?
+    };
+    let clan = player.clan(&api).await.ok();
+    let minutes = now().saturating_sub(started_at) / 60;
+    let data = RecentTemplate {
+        session: Some(format!("{}h {}m", minutes / 60, minutes % 60)),
+        ..RecentTemplate::new(&ctx, &stats, mode, clan, player)
+    };
+    let img = data.render(&ctx.data().client).await?;
+    ctx.send(
+        CreateReply::default()
+            .attachment(CreateAttachment::bytes(img, "image.png"))
+            .reply(true),
+    )
+    .await?;
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
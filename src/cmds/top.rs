@@ -80,42 +80,11 @@ async fn fetch_and_cache_kokomi(
     region: &Region,
     ship: &Ship,
 ) -> Result<Vec<ShipLeaderboardPlayer>, IsacError> {
-    let kokomi_token = ctx
-        .data()
-        .kokomi_api_token
-        .as_deref()
-        .ok_or(IsacInfo::GeneralError {
-            msg: "Kokomi API token not set in env".to_string(),
-        })?;
-
     let res_json = WowsApi::new(ctx)
-        .reqwest(
-            region.kokomi_url(format!(
-                "/api/external/ship/ranking/{ship_id}/",
-                ship_id = ship.ship_id
-            )),
-            |b| {
-                b.header("Access-Token", kokomi_token)
-                    .query(&[("size", "100"), ("dogtag", "0")])
-            },
-        )
-        .await?
-        .json::<serde_json::Value>()
+        .kokomi_ship_ranking(*region, ship.ship_id)
         .await?;
 
-    if res_json.get("status") != Some(&serde_json::Value::String("ok".to_string())) {
-        Err(IsacInfo::GeneralError {
-            msg: format!(
-                "Kokomi API error: {}",
-                res_json
-                    .get("message")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown error")
-            ),
-        })?;
-    }
-
-    let players_val = res_json["data"].as_array().ok_or(IsacInfo::GeneralError {
+    let players_val = res_json.as_array().ok_or(IsacInfo::GeneralError {
         msg: format!("No one on the leaderboard of `{}` yet", ship.name),
     })?;
 
@@ -231,7 +200,7 @@ async fn func_top(ctx: Context<'_>, region: Region, ship: Ship) -> Result<(), Er
             None
         } else if let Some(stats) =
             author_p
-                .single_ship(&api, &ship)
+                .single_ship(&api, &ship, false)
                 .await?
                 .and_then(|author_ship| {
                     author_ship.to_statistic(
@@ -395,7 +364,7 @@ async fn func_btop(ctx: Context<'_>, region: Region, ship: Ship) -> Result<(), E
             None
         } else if let Some(stats) =
             author_p
-                .single_ship(&api, &ship)
+                .single_ship(&api, &ship, false)
                 .await?
                 .and_then(|author_ship| {
                     author_ship.to_statistic(
@@ -19,7 +19,7 @@ use crate::{
     },
     template_data::{
         OverallCwTemplate, OverallCwTemplateSeason, OverallTemplate, OverallTemplateClass,
-        OverallTemplateDiv, OverallTemplateTier, Render, SingleShipTemplate,
+        OverallTemplateDiv, OverallTemplateMode, OverallTemplateTier, Render, SingleShipTemplate,
     },
     utils::wws_api::WowsApi,
 };
@@ -109,7 +109,10 @@ async fn func_ship(
     let api = WowsApi::new(ctx);
     let player = partial_player.full_player(&api).await?;
     let clan = player.clan(&api).await.ok();
-    let ship_stats = player.single_ship(&api, &ship).await?.unwrap_or_default(); // let it default, we will raise error belowed
+    let ship_stats = player
+        .single_ship(&api, &ship, mode.is_extra())
+        .await?
+        .unwrap_or_default(); // let it default, we will raise error belowed
 
     // getting player rank in the leaderboard
     let ranking = ctx
@@ -155,7 +158,8 @@ pub async fn func_wws(ctx: &Context<'_>, partial_player: PartialPlayer) -> Resul
     let clan = player.clan(&api).await.ok();
 
     // wws
-    let ships = player.all_ships(&api).await?;
+    // for the rows of `OverallTemplateMode`
+    let ships = player.all_ships(&api, true).await?;
     let div = OverallTemplateDiv::new(
         ships
             .to_statistic(&ctx.data().expected, Mode::Pvp)
@@ -169,6 +173,7 @@ pub async fn func_wws(ctx: &Context<'_>, partial_player: PartialPlayer) -> Resul
         ships
             .to_statistic(&ctx.data().expected, Mode::Div3)
             .unwrap_or_default(),
+        OverallTemplateMode::collect(&ships, &ctx.data().expected),
     );
     let class: OverallTemplateClass = ships
         .clone()
@@ -240,7 +245,10 @@ pub async fn func_wws(ctx: &Context<'_>, partial_player: PartialPlayer) -> Resul
                     .create_response(ctx, CreateInteractionResponse::Acknowledge)
                     .await;
                 // generate then send image
-                let img_tier = view.overall_data.render_tiers(&ctx.data().client).await?;
+                let img_tier = view
+                    .overall_data
+                    .render_as(&ctx.data().client, OverallTemplate::TIERS_RENDER_URL)
+                    .await?;
                 let _ok = msg
                     .edit(
                         ctx,
@@ -112,13 +112,16 @@ impl Args {
         }
     }
 
-    /// looking for number between 1 - 100, for `recent` days
-    pub fn parse_day(&mut self) -> Option<u64> {
-        if let Some(index) = self.0.iter().rposition(|key| key.parse::<u32>().is_ok()) {
-            self.remove(index).unwrap().parse::<u64>().ok()
-        } else {
-            None
-        }
+    /// looking for `from to` days, e.g. `.recent 14 7`, the second one is only taken if it's right after the first
+    pub fn parse_day_range(&mut self) -> Option<(u64, Option<u64>)> {
+        let is_day = |key: &String| key.parse::<u32>().is_ok();
+        let index = self.0.iter().position(is_day)?;
+        let day = self.remove(index).ok()?.parse::<u64>().ok()?;
+        let to_day = match self.0.get(index).is_some_and(is_day) {
+            true => self.remove(index).ok()?.parse::<u64>().ok(),
+            false => None,
+        };
+        Some((day, to_day))
     }
 
     /// searching for matching ships' name, Err if no argument left || No matched ship || Error when user picking
@@ -294,3 +297,21 @@ impl<'a, T: std::fmt::Display> PickView<'a, T> {
         vec![CreateActionRow::Buttons(btns)]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn day_range_takes_adjacent_numbers_only() {
+        let mut args = Args::from_str("14 7 pr").unwrap();
+        assert_eq!(args.parse_day_range(), Some((14, Some(7))));
+        assert_eq!(Vec::<String>::from(args), vec!["pr"]);
+
+        let mut args = Args::from_str("7 yamato 2").unwrap();
+        assert_eq!(args.parse_day_range(), Some((7, None)));
+        assert_eq!(Vec::<String>::from(args), vec!["yamato", "2"]);
+
+        assert_eq!(Args::from_str("yamato").unwrap().parse_day_range(), None);
+    }
+}
@@ -9,11 +9,12 @@ mod tasks;
 mod template_data;
 mod utils;
 
+use once_cell::sync::OnceCell;
 use poise::serenity_prelude::{
     self as serenity, ActivityData, ClientBuilder, ExecuteWebhook, UserId, Webhook,
 };
-use std::{collections::HashSet, env, ops::Deref, sync::Arc};
-use tokio::sync::mpsc::UnboundedSender;
+use std::{collections::HashSet, env, ops::Deref, sync::Arc, time::Duration};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tracing::{error, info, warn};
 use tracing_subscriber::{EnvFilter, prelude::*};
 
@@ -22,8 +23,7 @@ use crate::{
         Banner, ExpectedJs, GuildDefaultRegion, KokomiShipLeaderboard, Linked, LittleConstant,
         Patrons, ShipsPara, user_search_history::SearchCache,
     },
-    tasks::launch_renderer,
-    utils::{LoadSaveFromJson, error_handler},
+    utils::{LoadSaveFromJson, error_handler, metrics, render_cache::RENDER_CACHE},
 };
 
 // Types used by all command functions
@@ -38,6 +38,10 @@ async fn main() {
         .finish()
         .init();
 
+    // created before loading any file, so the reports during loading are buffered until the logger starts
+    let (webhook_tx, webhook_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let _ = WEBHOOK_TX.set(webhook_tx.clone());
+
     let is_product = &env::var("IS_PRODUCT")
         .expect("Missing IS_PRODUCT")
         .to_lowercase()
@@ -55,6 +59,7 @@ async fn main() {
         commands: vec![
             general::invite(),
             general::help(),
+            general::status(),
             owner::guilds(),
             owner::test(),
             owner::cache_size(),
@@ -63,6 +68,9 @@ async fn main() {
             owner::clan_season(),
             owner::update_src(),
             owner::who(),
+            owner::migrate_snapshots(),
+            owner::response_cache(),
+            owner::render_cache(),
             tools::roulette(),
             tools::history(),
             tools::map(),
@@ -79,6 +87,8 @@ async fn main() {
             clan::clan_hybrid(),
             clan_top::clan_top(),
             recent::recent_hybrid(),
+            session::session(),
+            export::export(),
             server_top::server_top_hybrid(),
         ],
         prefix_options: poise::PrefixFrameworkOptions {
@@ -86,11 +96,15 @@ async fn main() {
             mention_as_prefix: false,
             ..Default::default()
         },
+        pre_command: |ctx| Box::pin(metrics::command_started(ctx)),
+        post_command: |ctx| Box::pin(metrics::command_finished(ctx, None)),
         // The global error handler for all error cases that may occur
         on_error: |error| Box::pin(error_handler::on_error(error)),
         skip_checks_for_owners: true,
         ..Default::default()
     };
+    // fail fast instead of every `/recent` failing later
+    utils::snapshot_store::store().expect("Failed to open the snapshot store");
     let data = Data::new().await;
     let arc_data = data.clone();
     let (tx, rx) = std::sync::mpsc::channel::<()>();
@@ -140,7 +154,7 @@ async fn main() {
     }
 
     // a webhook logger, send the received message to discord logging channel
-    let webhook_tx = init_webhook_logger(bot.http.clone()).await;
+    init_webhook_logger(bot.http.clone(), webhook_rx);
 
     // update patreon
     tokio::spawn({
@@ -166,10 +180,37 @@ async fn main() {
         async move { tasks::expected_updater(client, expected, webhook_tx_new).await }
     });
 
+    // update recent snapshots
+    tokio::spawn({
+        let data = arc_data.clone();
+        let webhook_tx_new = webhook_tx.clone();
+        async move { tasks::snapshot_updater(data, webhook_tx_new).await }
+    });
+
+    // probe the upstreams for `/status`
+    tokio::spawn({
+        let data = arc_data.clone();
+        let webhook_tx_new = webhook_tx.clone();
+        async move { tasks::health_monitor(data, webhook_tx_new).await }
+    });
+
+    // Prometheus metrics endpoint
+    tokio::spawn(tasks::metrics_server());
+
+    tokio::spawn(RENDER_CACHE.load());
+
     info!("Launching renderer...");
-    let mut _renderer = launch_renderer().await; // it's used in linux specific code below
+    tokio::spawn({
+        let client = arc_data.client.clone();
+        let webhook_tx_new = webhook_tx.clone();
+        async move { tasks::renderer_supervisor(client, webhook_tx_new).await }
+    });
 
     tokio::spawn(async move {
+        // don't take commands before the renderer can draw them
+        if !tasks::wait_renderer_ready(Duration::from_secs(120)).await {
+            warn!("Renderer is not ready, starting the bot anyway");
+        }
         if let Err(err) = bot.start_autosharded().await {
             error!("Client error: {:?}", err);
         }
@@ -182,10 +223,7 @@ async fn main() {
         let _ = webhook_tx.send("Bot shutting down...".into());
     }
     // close renderer
-    #[cfg(target_os = "linux")]
-    if let Some(renderer_pid) = _renderer.id() {
-        unsafe { libc::kill(renderer_pid as i32, libc::SIGTERM) };
-    }
+    tasks::stop_renderer();
     shard_manager.shutdown_all().await;
 }
 
@@ -244,9 +282,39 @@ impl DataInner {
     }
 }
 
+#[cfg(test)]
+impl Data {
+    /// an empty [`Data`] without touching the disk, for the tests with [`utils::mock_upstream`]
+    ///
+    /// leaked since some fields save themselves on drop
+    pub fn mock() -> &'static Self {
+        Box::leak(Box::new(Self {
+            inner: Arc::new(DataInner {
+                client: reqwest::Client::new(),
+                patron: Default::default(),
+                expected: Default::default(),
+                ships: Default::default(),
+                constant: Default::default(),
+                link: Default::default(),
+                wg_api_token: "mock".to_string(),
+                kokomi_api_token: Some("mock".to_string()),
+                guild_default: Default::default(),
+                banner: Default::default(),
+                kleaderboard: Default::default(),
+                cache: tokio::sync::Mutex::new(SearchCache::new()),
+            }),
+        }))
+    }
+}
+
+/// the sender of the webhook logger, for places without the `webhook_tx` passed in
+pub static WEBHOOK_TX: OnceCell<UnboundedSender<String>> = OnceCell::new();
+
 /// A webhook logger, send the received message to discord logging channel
-async fn init_webhook_logger(webhook_http: Arc<serenity::Http>) -> UnboundedSender<String> {
-    let (webhook_tx, mut webhook_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+fn init_webhook_logger(
+    webhook_http: Arc<serenity::Http>,
+    mut webhook_rx: UnboundedReceiver<String>,
+) {
     tokio::spawn(async move {
         let err_webhook = env::var("ERR_WEB_HOOK");
         match err_webhook {
@@ -269,5 +337,4 @@ async fn init_webhook_logger(webhook_http: Arc<serenity::Http>) -> UnboundedSend
             }
         }
     });
-    webhook_tx
 }
@@ -47,6 +47,9 @@ pub use clan_detail::*;
 mod recent;
 pub use recent::*;
 
+mod retention;
+pub use retention::*;
+
 mod clan_battles_season_stats;
 pub use clan_battles_season_stats::*;
 
@@ -2,7 +2,7 @@
 
 use serde::de::{self, MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
-use std::fmt;
+use std::{collections::HashMap, fmt};
 
 use crate::utils::{IsacError, IsacInfo};
 
@@ -36,6 +36,24 @@ impl Status {
     }
 }
 
+/// the response of the official WG API, see [`crate::utils::wg_query::WgQuery`]
+#[derive(Debug, Deserialize)]
+pub struct WgApiRes<T> {
+    #[serde(flatten)]
+    pub status: Status,
+    /// keyed by the requested ids, null if the id is not found
+    #[serde(default = "HashMap::new")] // its missing if an error occur
+    pub data: HashMap<u64, Option<T>>,
+}
+
+impl<T> WgApiRes<T> {
+    /// check the status is "ok" before getting the data
+    pub fn data(self) -> Result<HashMap<u64, Option<T>>, IsacError> {
+        self.status.error_for_status()?;
+        Ok(self.data)
+    }
+}
+
 /// Catching the error message in api
 /// there's two form, string or map
 ///
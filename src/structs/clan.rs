@@ -147,6 +147,19 @@ struct PlayerClanDataClan {
     members_count: u32,
 }
 
+// https://api.worldofwarships.asia/wows/clans/accountinfo/?extra=clan
+/// player's clan in the official api, for looking up many players at once
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WgClanAccountInfo {
+    pub clan_id: Option<u64>, // null if the player is not in a clan
+    pub clan: Option<WgClanAccountInfoClan>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WgClanAccountInfoClan {
+    pub tag: ClanTag,
+}
+
 // https://clans.worldofwarships.asia/api/clanbase/2000007634/claninfo/
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ClanInfoAPIRes {
@@ -159,7 +172,7 @@ impl From<ClanInfoAPIRes> for Clan {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Clan {
     #[serde(rename(serialize = "info", deserialize = "clan"))]
     pub info: ClanInfo,
@@ -167,7 +180,7 @@ pub struct Clan {
     pub stats: ClanStats,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClanInfo {
     pub members_count: u32,
     pub max_members_count: u32,
@@ -180,12 +193,12 @@ pub struct ClanInfo {
     pub region: Region, // adding it manually in clan_deatail() after deserialized
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClanStats {
     pub ratings: HashSet<ClanStatsSeason>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
 pub struct ClanStatsSeason {
     #[serde(flatten)]
     pub now: ClanStatsRating,
@@ -233,7 +246,7 @@ impl From<ClanStatsSeason> for ClanTemplateSeason {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Hash, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash, Default)]
 pub struct ClanStatsRating {
     pub league: ClanLeague,     // 0, 1, 2, 3, 4
     pub division: ClanDivision, // 1, 2, 3
@@ -251,7 +264,7 @@ impl From<ClanStatsRating> for ClanTemplateSeasonValue {
     }
 }
 
-#[derive(Serialize, Deserialize_repr, Debug, Eq, PartialEq, Hash, Default)]
+#[derive(Serialize, Deserialize_repr, Debug, Clone, Eq, PartialEq, Hash, Default)]
 #[repr(u8)]
 pub enum ClanLeague {
     Hurricane = 0,
@@ -275,7 +288,7 @@ impl ClanLeague {
 }
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Serialize, Deserialize_repr, Debug, Eq, PartialEq, Hash, Default)]
+#[derive(Serialize, Deserialize_repr, Debug, Clone, Eq, PartialEq, Hash, Default)]
 #[repr(u8)]
 pub enum ClanDivision {
     I = 1,
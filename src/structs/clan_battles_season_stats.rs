@@ -1,39 +1,13 @@
 // the struct for player's clan battles seasons stats
 // https://api.worldofwarships.asia/wows/clans/seasonstats/
 
-use std::collections::HashMap;
-
 use serde::{Deserialize, Serialize};
 
-use super::api;
-use crate::utils::IsacError;
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct PlayerClanBattleAPIRes {
-    #[serde(flatten)]
-    pub status: api::Status,
-    pub data: HashMap<u64, PlayerClanBattle>, // only one in the map
-}
-
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct PlayerClanBattle {
     pub seasons: Vec<PlayerClanBattleSeason>,
 }
 
-impl TryFrom<PlayerClanBattleAPIRes> for PlayerClanBattle {
-    type Error = IsacError;
-
-    fn try_from(value: PlayerClanBattleAPIRes) -> Result<Self, Self::Error> {
-        value.status.error_for_status()?;
-        Ok(value
-            .data
-            .into_iter()
-            .next()
-            .map(|s| s.1)
-            .unwrap_or(PlayerClanBattle { seasons: vec![] }))
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PlayerClanBattleSeason {
     pub season_id: u32,
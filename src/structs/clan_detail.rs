@@ -1,9 +1,6 @@
-use std::collections::HashMap;
-
 use serde::{Deserialize, Serialize};
 
-use super::{ClanTag, api};
-use crate::utils::IsacError;
+use super::ClanTag;
 
 // #[derive(Debug, Deserialize, Serialize)]
 // pub struct ClanDetailMember {
@@ -36,22 +33,6 @@ pub struct ClanDetail {
     pub description: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct ClanDetailAPIRes {
-    #[serde(flatten)]
-    status: api::Status,
-    // meta: Meta,
-    #[serde(default)] // its missing if an error occur
-    pub data: HashMap<u64, ClanDetail>, // only one in the map
-}
-impl ClanDetailAPIRes {
-    /// check the status is "ok" before getting the data
-    pub fn data(self) -> Result<ClanDetail, IsacError> {
-        self.status.error_for_status()?;
-        Ok(self.data.into_values().next().unwrap())
-    }
-}
-
 #[test]
 fn clan_detail_res_can_deserialize() {
     let err_json = r#"
@@ -64,7 +45,7 @@ fn clan_detail_res_can_deserialize() {
             "value": null
         }
     }"#;
-    match serde_json::from_str::<ClanDetailAPIRes>(err_json) {
+    match serde_json::from_str::<super::api::WgApiRes<ClanDetail>>(err_json) {
         Ok(s) => {
             if !s.status.ok() {
                 println!("{}", s.status.err_msg());
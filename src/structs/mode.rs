@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use std::hash::Hash;
-use strum::EnumIter;
+use strum::{EnumIter, IntoEnumIterator};
 
 #[derive(
     Debug,
@@ -28,9 +28,44 @@ pub enum Mode {
     Div3,
     #[serde(rename = "rank_solo")]
     Rank,
+    #[serde(rename = "rank_div2")]
+    #[name = "Rank Div2"]
+    RankDiv2,
+    #[serde(rename = "rank_div3")]
+    #[name = "Rank Div3"]
+    RankDiv3,
+    #[serde(rename = "pve")]
+    #[name = "Co-op"]
+    Coop,
+    #[serde(rename = "oper_solo")]
+    #[name = "Operation"]
+    Oper,
+    #[serde(rename = "oper_div")]
+    #[name = "Operation Div"]
+    OperDiv,
 }
 
 impl Mode {
+    /// the random battle modes, which come with the division breakdown
+    pub fn is_pvp(&self) -> bool {
+        matches!(self, Mode::Pvp | Mode::Solo | Mode::Div2 | Mode::Div3)
+    }
+
+    /// the modes fetched only when a view needs them, see [`crate::utils::wws_api::WowsApi::statistics_of_player_ships`]
+    ///
+    /// each mode is one more vortex request per player
+    pub fn is_extra(&self) -> bool {
+        !matches!(
+            self,
+            Mode::Pvp | Mode::Solo | Mode::Div2 | Mode::Div3 | Mode::Rank
+        )
+    }
+
+    /// the modes outside the random battles, e.g. ranked, co-op and operations
+    pub fn others() -> impl Iterator<Item = Self> {
+        Self::iter().filter(|mode| !mode.is_pvp())
+    }
+
     /// just uppercased
     pub fn upper(&self) -> &'static str {
         match self {
@@ -39,6 +74,11 @@ impl Mode {
             Mode::Div2 => "DIV2",
             Mode::Div3 => "DIV3",
             Mode::Rank => "RANK",
+            Mode::RankDiv2 => "RANK DIV2",
+            Mode::RankDiv3 => "RANK DIV3",
+            Mode::Coop => "CO-OP",
+            Mode::Oper => "OPER",
+            Mode::OperDiv => "OPER DIV",
         }
     }
     /// return its name in api
@@ -54,6 +94,8 @@ impl Mode {
     ///
     /// [`Mode::Rank`] -> rank_solo
     ///
+    /// [`Mode::Coop`] -> pve
+    ///
     pub fn api_name(&self) -> &'static str {
         match self {
             Mode::Pvp => "pvp",
@@ -61,6 +103,11 @@ impl Mode {
             Mode::Div2 => "pvp_div2",
             Mode::Div3 => "pvp_div3",
             Mode::Rank => "rank_solo",
+            Mode::RankDiv2 => "rank_div2",
+            Mode::RankDiv3 => "rank_div3",
+            Mode::Coop => "pve",
+            Mode::Oper => "oper_solo",
+            Mode::OperDiv => "oper_div",
         }
     }
 
@@ -71,6 +118,11 @@ impl Mode {
             "div2" => Self::Div2,
             "div3" => Self::Div3,
             "rank" | "ranked" => Self::Rank,
+            "rankdiv2" => Self::RankDiv2,
+            "rankdiv3" => Self::RankDiv3,
+            "coop" | "co-op" | "pve" => Self::Coop,
+            "oper" | "operation" => Self::Oper,
+            "operdiv" => Self::OperDiv,
             _ => None?,
         })
     }
@@ -89,6 +141,8 @@ impl Mode {
     ///
     /// [`Mode::Rank`] -> (rank)
     ///
+    /// [`Mode::Coop`] -> (co-op)
+    ///
     pub fn render_name(&self) -> &'static str {
         match self {
             Mode::Pvp => "",
@@ -96,6 +150,11 @@ impl Mode {
             Mode::Div2 => "(div2)",
             Mode::Div3 => "(div3)",
             Mode::Rank => "(rank)",
+            Mode::RankDiv2 => "(rank div2)",
+            Mode::RankDiv3 => "(rank div3)",
+            Mode::Coop => "(co-op)",
+            Mode::Oper => "(oper)",
+            Mode::OperDiv => "(oper div)",
         }
     }
 }
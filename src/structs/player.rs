@@ -53,19 +53,24 @@ impl PartialPlayer {
             .await?
             .ok_or(IsacInfo::UserNoClan.into())
     }
-    /// all ships' statistics
-    pub async fn all_ships(&self, api: &WowsApi<'_>) -> Result<ShipStatsCollection, IsacError> {
-        api.statistics_of_player_ships(self.region, self.uid, None)
+    /// all ships' statistics, with the modes of [`crate::structs::Mode::is_extra`] if `extra_modes`
+    pub async fn all_ships(
+        &self,
+        api: &WowsApi<'_>,
+        extra_modes: bool,
+    ) -> Result<ShipStatsCollection, IsacError> {
+        api.statistics_of_player_ships(self.region, self.uid, None, extra_modes)
             .await
     }
-    /// specific ship's statistics
+    /// specific ship's statistics, with the modes of [`crate::structs::Mode::is_extra`] if `extra_modes`
     pub async fn single_ship(
         &self,
         api: &WowsApi<'_>,
         ship: &Ship,
+        extra_modes: bool,
     ) -> Result<Option<ShipModeStatsPair>, IsacError> {
         let ship_pair = api
-            .statistics_of_player_ships(self.region, self.uid, Some(ship.ship_id))
+            .statistics_of_player_ships(self.region, self.uid, Some(ship.ship_id), extra_modes)
             .await?
             .get_ship(&ship.ship_id);
         Ok(ship_pair)
@@ -157,6 +162,19 @@ impl TryFrom<VortexPlayerAPIRes> for VortexPlayer {
     }
 }
 
+// https://api.worldofwarships.asia/wows/account/info/
+/// player in the official api, for looking up many players at once. No dogtag here
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WgAccountInfo {
+    pub nickname: String,
+    #[serde(default)]
+    pub hidden_profile: bool,
+    #[serde(default)]
+    pub leveling_points: u64,
+    #[serde(default)]
+    pub karma: Option<u64>, // missing without the player's access token
+}
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct PlayerDogTag {
     texture_id: u64,
@@ -1,24 +1,67 @@
 use std::{
-    collections::BTreeMap,
-    path::PathBuf,
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Weak},
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use serde::{Deserialize, Serialize};
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use serde_json::Value;
+use tokio::sync::{Mutex, OwnedMutexGuard};
 
-use crate::structs::{PartialPlayer, ShipStatsCollection};
+use crate::{
+    Error,
+    structs::{
+        PartialPlayer, Region, RetentionPolicy, ShipStatsAnomaly, ShipStatsCollection,
+        ShipStatsDelta,
+    },
+    utils::{IsacError, IsacInfo, Migration, migrate, snapshot_store},
+};
+
+/// the upgrades of [`PlayerSnapshots`]'s file, the version written is its length
+///
+/// - 0: every snapshot is a full [`ShipStatsCollection`], maybe with the python era shapes
+/// - 1: the oldest snapshot in full, the rest are [`ShipStatsDelta`] from the previous one
+const SNAPSHOTS_MIGRATIONS: &[Migration] = &[migrate_v0];
+
+/// the per player locks of [`PlayerSnapshots::lock`], dropped when nobody holds them
+static LOCKS: Lazy<parking_lot::Mutex<HashMap<PartialPlayer, Weak<Mutex<()>>>>> =
+    Lazy::new(Default::default);
 
-#[derive(Debug, Serialize, Deserialize)]
+/// proof of holding a player's [`PlayerSnapshots::lock`]
+pub type SnapshotsGuard = OwnedMutexGuard<()>;
+
+#[derive(Debug, Clone)]
 pub struct PlayerSnapshots {
     // PartialPlayer will get in Self::load()
-    #[serde(default, skip_serializing)]
     pub player: PartialPlayer,
     pub last_update_at: u64, // unix timestamp
     pub last_request: PlayerSnapshotsType,
     pub data: BTreeMap<u64, ShipStatsCollection>,
+    /// the `/session start` marker, kept apart from the daily `data` so the pruning won't touch it
+    pub session: Option<(u64, ShipStatsCollection)>,
 }
 
 impl PlayerSnapshots {
+    /// lock the player's snapshots, hold it from loading to saving,
+    /// so the updater and the commands won't overwrite each other's changes
+    pub async fn lock(player: PartialPlayer) -> SnapshotsGuard {
+        let lock = {
+            let mut locks = LOCKS.lock();
+            locks.retain(|_, lock| lock.strong_count() > 0);
+            match locks.get(&player).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(Mutex::new(()));
+                    locks.insert(player, Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+        lock.lock_owned().await
+    }
+
     /// try to get the given date data first, then find the closest one, None if no matched
     pub async fn get_date(&self, timestamp: &u64) -> Option<(u64, ShipStatsCollection)> {
         if let Some((k, v)) = self.data.get_key_value(timestamp) {
@@ -31,6 +74,15 @@ impl PlayerSnapshots {
         }
     }
 
+    /// whether the modes of [`crate::structs::Mode::is_extra`] are tracked for the player
+    ///
+    /// they cost more requests, so only fetched for the players who played them
+    pub fn tracks_extra_modes(&self) -> bool {
+        self.data
+            .last_key_value()
+            .is_some_and(|(_, ships)| ships.has_extra_modes())
+    }
+
     /// get the latest snapshot, it should always be Some()
     pub fn latest_snapshot(&self) -> Option<ShipStatsCollection> {
         self.data.last_key_value().map(|(_, v)| v.clone())
@@ -45,6 +97,71 @@ impl PlayerSnapshots {
             .collect()
     }
 
+    /// diff each adjacent pair of snapshots from `start` to `end`, keyed by the later one's timestamp
+    ///
+    /// `end` is given explicitly so the current ships can be the last one, None if no battles that day
+    pub fn timeline(
+        &self,
+        start: u64,
+        end: (u64, &ShipStatsCollection),
+    ) -> Vec<(u64, Option<ShipStatsCollection>)> {
+        self.data
+            .range(start..end.0)
+            .map(|(date, ships)| (*date, ships))
+            .chain(std::iter::once(end))
+            .tuple_windows()
+            .map(|((_, old), (date, new))| (date, new.compare(old.clone())))
+            .collect()
+    }
+
+    /// false if ISAC stopped tracking the player, see [`RetentionPolicy::inactive_days`]
+    pub fn is_active(&self, now: u64) -> bool {
+        match self.last_request {
+            PlayerSnapshotsType::Premium => true,
+            PlayerSnapshotsType::Normal(timestamp) => {
+                now.saturating_sub(timestamp) < RetentionPolicy::get().inactive_days * 86400
+            }
+        }
+    }
+
+    /// remove the snapshots which are over the [`RetentionPolicy`] or the same as `current`,
+    /// then thin the older ones to weekly
+    pub fn prune(&mut self, now: u64, current: Option<&ShipStatsCollection>) {
+        let policy = RetentionPolicy::get();
+        let max_day = policy.max_day(&self.last_request);
+        self.data.retain(|date, ships| {
+            now.saturating_sub(*date) < max_day * 86400 && Some(&*ships) != current
+        });
+        policy.thin(now, &mut self.data);
+    }
+
+    /// check `current` against the latest snapshot, and move the abnormal ships in the older
+    /// snapshots by the jump, so the battles before it are kept and only the jump is skipped
+    ///
+    /// a ship that can't be moved starts over from `current`, and a disappeared one is removed
+    pub fn rebaseline(&mut self, now: u64, current: &ShipStatsCollection) -> Vec<ShipStatsAnomaly> {
+        let Some((date, latest)) = self.data.last_key_value() else {
+            return vec![];
+        };
+        let anomalies = current.anomalies(latest, now.saturating_sub(*date));
+        let latest = latest.clone();
+        for ship_id in anomalies.iter().map(ShipStatsAnomaly::ship_id).unique() {
+            let pairs = current.0.get(&ship_id).zip(latest.0.get(&ship_id));
+            for ships in self.data.values_mut() {
+                let Some((current, latest)) = pairs else {
+                    ships.0.remove(&ship_id);
+                    continue;
+                };
+                let old = ships.0.get(&ship_id).cloned().unwrap_or_default();
+                let shifted = old
+                    .shifted(latest, current)
+                    .unwrap_or_else(|| current.clone());
+                ships.0.insert(ship_id, shifted);
+            }
+        }
+        anomalies
+    }
+
     /// update the Self.last_request to now
     pub fn update_last_request(&mut self, is_premium: bool) {
         let now = SystemTime::now()
@@ -59,22 +176,23 @@ impl PlayerSnapshots {
     }
 
     /// load the player's recent data, return None if he is not inside
-    pub async fn load(player: PartialPlayer) -> Option<Self> {
-        let path = Self::get_path(&player);
-        // std::fs::File::open() is as fast as path.exists()
-        if let Ok(file) = std::fs::File::open(&path) {
-            let mut data: PlayerSnapshots = tokio::task::spawn_blocking(move || {
-                let json_str = std::io::read_to_string(file).unwrap();
-                serde_json::from_str(&json_str)
-            })
+    pub async fn load(player: PartialPlayer) -> Result<Option<Self>, IsacError> {
+        tokio::task::spawn_blocking(move || snapshot_store::store()?.load(player))
             .await
-            .unwrap()
-            .unwrap_or_else(|err| panic!("Failed to deserialize file: {:?}\n Err: {err}", path,));
-            data.player = player;
-            Some(data)
-        } else {
-            None
-        }
+            .map_err(Error::from)
+            .and_then(|res| res)
+            .map_err(|err| Self::store_error(player, "load", err))
+    }
+
+    /// only load the player's latest snapshot, return None if he is not inside
+    pub async fn load_latest(
+        player: PartialPlayer,
+    ) -> Result<Option<ShipStatsCollection>, IsacError> {
+        tokio::task::spawn_blocking(move || snapshot_store::store()?.latest(player))
+            .await
+            .map_err(Error::from)
+            .and_then(|res| res)
+            .map_err(|err| Self::store_error(player, "load", err))
     }
 
     /// add the given record into the snapshot, and update the `last_updated_at`
@@ -94,37 +212,299 @@ impl PlayerSnapshots {
             last_update_at: 0,
             last_request: PlayerSnapshotsType::Normal(0),
             data: Default::default(),
+            session: None,
         }
     }
 
     /// save player data
-    pub async fn save(&self) {
-        let path = Self::get_path(&self.player);
+    pub async fn save(&self) -> Result<(), IsacError> {
+        let snapshots = self.clone();
+        tokio::task::spawn_blocking(move || snapshot_store::store()?.save(&snapshots))
+            .await
+            .map_err(Error::from)
+            .and_then(|res| res)
+            .map_err(|err| Self::store_error(self.player, "save", err))
+    }
 
-        let json_bytes = serde_json::to_vec(&self).unwrap_or_else(|err| {
-            panic!(
-                "Failed to serialize struct: {:?} to JSON. Err: {err}",
-                std::any::type_name::<Self>(),
-            )
-        });
-        crate::utils::save_file_with_lock(path, &json_bytes);
+    /// log the store error, and tell the user to try again instead of the details
+    fn store_error(player: PartialPlayer, action: &str, err: Error) -> IsacError {
+        tracing::error!("Failed to {action} snapshots: {player:?}, Err: {err}");
+        IsacInfo::GeneralError {
+            msg: "Failed to access the recent data, please try again later".to_string(),
+        }
+        .into()
     }
 
-    /// get player's file path
-    fn get_path(player: &PartialPlayer) -> PathBuf {
-        let mut path = PathBuf::from("./recent_DB/players/");
-        path.push(player.region.lower());
-        path.push(format!("{}.json", player.uid));
-        path
+    /// delete player data, for those players with no data left
+    pub async fn delete(&self) -> Result<(), IsacError> {
+        let player = self.player;
+        tokio::task::spawn_blocking(move || snapshot_store::store()?.delete(player))
+            .await
+            .map_err(Error::from)
+            .and_then(|res| res)
+            .map_err(|err| Self::store_error(player, "delete", err))
+    }
+
+    /// all the players in the database of the given region
+    pub async fn players(region: Region) -> Vec<PartialPlayer> {
+        tokio::task::spawn_blocking(move || snapshot_store::store()?.players(region))
+            .await
+            .map_err(Error::from)
+            .and_then(|res| res)
+            .unwrap_or_else(|err| {
+                tracing::warn!("Failed to list snapshots players: {region}, Err: {err}");
+                vec![]
+            })
     }
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum PlayerSnapshotsType {
-    #[serde(alias = "prime")]
     Premium,
     Normal(u64),
 }
 
-// #[derive(Debug, Serialize, Deserialize)]
-// pub struct PlayerSnapshotsData { }
+/// the serialized form of [`PlayerSnapshots`]
+#[derive(Serialize)]
+struct PlayerSnapshotsFile<'a> {
+    version: usize,
+    last_update_at: u64,
+    last_request: PlayerSnapshotsType,
+    base: Option<(u64, &'a ShipStatsCollection)>,
+    deltas: BTreeMap<u64, ShipStatsDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<(u64, &'a ShipStatsCollection)>,
+}
+
+/// the latest format, older ones are upgraded by [`SNAPSHOTS_MIGRATIONS`] first
+#[derive(Deserialize)]
+struct PlayerSnapshotsFileAny {
+    last_update_at: u64,
+    last_request: PlayerSnapshotsType,
+    #[serde(default)]
+    base: Option<(u64, ShipStatsCollection)>,
+    #[serde(default)]
+    deltas: BTreeMap<u64, ShipStatsDelta>,
+    #[serde(default)]
+    session: Option<(u64, ShipStatsCollection)>,
+}
+
+/// the oldest snapshot in full, and the deltas of the rest from the previous one
+fn to_deltas(
+    data: &BTreeMap<u64, ShipStatsCollection>,
+) -> (
+    Option<(u64, &ShipStatsCollection)>,
+    BTreeMap<u64, ShipStatsDelta>,
+) {
+    let mut deltas = BTreeMap::new();
+    let mut iter = data.iter();
+    let base = iter.next();
+    let mut last = base.map(|(_, ships)| ships);
+    for (date, ships) in iter {
+        if let Some(last) = last {
+            deltas.insert(*date, ships.delta(last));
+        }
+        last = Some(ships);
+    }
+    (base.map(|(date, ships)| (*date, ships)), deltas)
+}
+
+impl Serialize for PlayerSnapshots {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (base, deltas) = to_deltas(&self.data);
+        PlayerSnapshotsFile {
+            version: SNAPSHOTS_MIGRATIONS.len(),
+            last_update_at: self.last_update_at,
+            last_request: self.last_request,
+            base,
+            deltas,
+            session: self.session.as_ref().map(|(date, ships)| (*date, ships)),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// version 0 to 1, the full snapshots into deltas, and the legacy shapes written by the python
+/// side, `"prime"` for [`PlayerSnapshotsType::Premium`] and `"battles"` for `battles_count`
+fn migrate_v0(mut value: Value) -> Result<Value, String> {
+    let file = value.as_object_mut().ok_or("not a map")?;
+    if let Some(last_request) = file.get_mut("last_request")
+        && last_request == "prime"
+    {
+        *last_request = Value::from("Premium");
+    }
+    let mut data = file.remove("data").unwrap_or_default();
+    let all_stats = data
+        .as_object_mut()
+        .into_iter()
+        .flat_map(|snapshots| snapshots.values_mut())
+        .filter_map(Value::as_object_mut)
+        .flat_map(|ships| ships.values_mut())
+        .filter_map(Value::as_object_mut)
+        .flat_map(|modes| modes.values_mut())
+        .filter_map(Value::as_object_mut);
+    for stats in all_stats {
+        if let Some(battles) = stats.remove("battles") {
+            stats.entry("battles_count").or_insert(battles);
+        }
+    }
+    let data: BTreeMap<u64, ShipStatsCollection> = match data {
+        Value::Null => BTreeMap::new(),
+        data => serde_json::from_value(data).map_err(|err| err.to_string())?,
+    };
+    let (base, deltas) = to_deltas(&data);
+    file.insert(
+        "base".to_string(),
+        serde_json::to_value(base).map_err(|err| err.to_string())?,
+    );
+    file.insert(
+        "deltas".to_string(),
+        serde_json::to_value(deltas).map_err(|err| err.to_string())?,
+    );
+    Ok(value)
+}
+
+impl<'de> Deserialize<'de> for PlayerSnapshots {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        // files without a version are the full snapshots of version 0
+        let version = value
+            .get("version")
+            .map(|version| version.as_u64().ok_or("invalid version"))
+            .transpose()
+            .map_err(de::Error::custom)?
+            .unwrap_or(0);
+        let value =
+            migrate(version as usize, value, SNAPSHOTS_MIGRATIONS).map_err(de::Error::custom)?;
+        let file = PlayerSnapshotsFileAny::deserialize(value).map_err(de::Error::custom)?;
+        let mut data = BTreeMap::new();
+        if let Some((date, mut ships)) = file.base {
+            data.insert(date, ships.clone());
+            for (date, delta) in file.deltas {
+                ships.apply(delta);
+                data.insert(date, ships.clone());
+            }
+        }
+        Ok(Self {
+            player: Default::default(),
+            last_update_at: file.last_update_at,
+            last_request: file.last_request,
+            data,
+            session: file.session,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::PlayerSnapshots;
+    use crate::structs::{PartialPlayer, Region, ShipStatsAnomaly, ShipStatsCollection};
+
+    #[tokio::test]
+    async fn lock_is_per_player() {
+        let player = PartialPlayer {
+            region: Region::Asia,
+            uid: 1,
+        };
+        let other = PartialPlayer { uid: 2, ..player };
+        let guard = PlayerSnapshots::lock(player).await;
+        // another player is not blocked
+        let _other = PlayerSnapshots::lock(other).await;
+        let waiting =
+            tokio::time::timeout(Duration::from_millis(20), PlayerSnapshots::lock(player));
+        assert!(waiting.await.is_err());
+        drop(guard);
+        let _guard = PlayerSnapshots::lock(player).await;
+    }
+
+    #[test]
+    fn snapshots_delta_roundtrip() {
+        let old_format = r#"{"last_update_at":3,"last_request":"Premium","data":{
+            "1":{"4277090288":{"pvp":{"battles_count":1,"wins":1,"damage_dealt":1,"frags":1,"planes_killed":1,"original_exp":1,"art_agro":1,"scouting_damage":1,"shots_by_main":1,"hits_by_main":1}}},
+            "2":{"4277090288":{"pvp":{"battles_count":2,"wins":1,"damage_dealt":2,"frags":1,"planes_killed":1,"original_exp":2,"art_agro":2,"scouting_damage":2,"shots_by_main":2,"hits_by_main":2}}},
+            "3":{}
+        }}"#;
+        let snapshots: PlayerSnapshots = serde_json::from_str(old_format).unwrap();
+        let new_format = serde_json::to_string(&snapshots).unwrap();
+        assert!(new_format.contains(r#""version":1"#));
+
+        let reloaded: PlayerSnapshots = serde_json::from_str(&new_format).unwrap();
+        assert_eq!(reloaded.data, snapshots.data);
+        assert_eq!(reloaded.data.len(), 3);
+    }
+
+    #[test]
+    fn legacy_snapshots_get_migrated() {
+        let legacy_format = r#"{"last_update_at":1,"last_request":"prime","data":{
+            "1":{"4277090288":{"pvp":{"battles":2,"wins":1,"damage_dealt":2,"frags":1,"planes_killed":1,"original_exp":2,"scouting_damage":2,"shots_by_main":2,"hits_by_main":2}}}
+        }}"#;
+        let snapshots: PlayerSnapshots = serde_json::from_str(legacy_format).unwrap();
+        assert!(matches!(
+            snapshots.last_request,
+            super::PlayerSnapshotsType::Premium
+        ));
+        let saved = serde_json::to_string(&snapshots).unwrap();
+        assert!(saved.contains(r#""battles_count":2"#));
+        assert!(!saved.contains("prime"));
+        assert!(saved.contains(r#""version":1"#));
+        // written by a newer build
+        let newer = r#"{"version":2,"last_update_at":1,"last_request":"Premium"}"#;
+        assert!(serde_json::from_str::<PlayerSnapshots>(newer).is_err());
+    }
+
+    #[test]
+    fn rollback_gets_rebaselined() {
+        let snapshots = r#"{"last_update_at":1,"last_request":"Premium","data":{
+            "1":{"4277090288":{"pvp":{"battles_count":2,"wins":1,"damage_dealt":2,"frags":1,"planes_killed":1,"original_exp":2,"art_agro":2,"scouting_damage":2,"shots_by_main":2,"hits_by_main":2}}}
+        }}"#;
+        let current = r#"{"4277090288":{"pvp":{"battles_count":1,"wins":1,"damage_dealt":1,"frags":1,"planes_killed":1,"original_exp":1,"art_agro":1,"scouting_damage":1,"shots_by_main":1,"hits_by_main":1}}}"#;
+        let mut snapshots: PlayerSnapshots = serde_json::from_str(snapshots).unwrap();
+        let current: ShipStatsCollection = serde_json::from_str(current).unwrap();
+
+        let anomalies = snapshots.rebaseline(86400, &current);
+        assert!(matches!(
+            anomalies.as_slice(),
+            [ShipStatsAnomaly::Decreased { .. }]
+        ));
+        assert_eq!(snapshots.data[&1], current);
+    }
+
+    #[test]
+    fn battles_before_jump_are_kept() {
+        let stats = |battles: u64| {
+            format!(
+                r#"{{"4277090288":{{"pvp":{{"battles_count":{battles},"wins":{battles},"damage_dealt":0,"frags":0,"planes_killed":0,"original_exp":0,"art_agro":0,"scouting_damage":0,"shots_by_main":0,"hits_by_main":0}}}}}}"#
+            )
+        };
+        // 2 battles on day 1, then 1000 battles merged in within a day
+        let snapshots = format!(
+            r#"{{"last_update_at":86400,"last_request":"Premium","data":{{"0":{},"86400":{}}}}}"#,
+            stats(10),
+            stats(12)
+        );
+        let mut snapshots: PlayerSnapshots = serde_json::from_str(&snapshots).unwrap();
+        let current: ShipStatsCollection = serde_json::from_str(&stats(1012)).unwrap();
+
+        let anomalies = snapshots.rebaseline(86400 * 2, &current);
+        assert!(matches!(
+            anomalies.as_slice(),
+            [ShipStatsAnomaly::ImpossibleJump { battles: 1000, .. }]
+        ));
+        let battles = |old: &ShipStatsCollection| {
+            current
+                .compare(old.clone())
+                .map(|diff| {
+                    diff.0
+                        .values()
+                        .map(|pair| pair.0.values().map(|s| s.values()[0].unwrap()).sum::<u64>())
+                        .sum::<u64>()
+                })
+                .unwrap_or_default()
+        };
+        // the 2 battles of day 1 are still there, the jump is not
+        assert_eq!(battles(&snapshots.data[&0]), 2);
+        assert_eq!(battles(&snapshots.data[&86400]), 0);
+    }
+}
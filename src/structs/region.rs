@@ -1,10 +1,18 @@
 use crate::{Context, utils::LoadSaveFromJson};
 
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
 use poise::serenity_prelude::GuildId;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::HashMap, env, fmt::Display};
+
+/// if set, the upstream urls become `{base}/{upstream}/{region}`, e.g. `{base}/vortex/asia/api/...`
+///
+/// set `UPSTREAM_BASE_URL` in `.env` to point the bot to a local mock server
+static BASE_URL_OVERRIDE: Lazy<RwLock<Option<String>>> =
+    Lazy::new(|| RwLock::new(env::var("UPSTREAM_BASE_URL").ok()));
 
 /// wows server
 #[derive(
@@ -74,7 +82,7 @@ impl Region {
             Region::Na => "https://vortex.worldofwarships.com",
             Region::Eu => "https://vortex.worldofwarships.eu",
         };
-        Self::_construct_url(base, sub_url)
+        Self::_construct_url(&self._base("vortex", base), sub_url)
     }
     /// official api url ( https://api.worldofwarships.asia )
     pub fn api_url(&self, sub_url: impl AsRef<str>) -> Url {
@@ -83,7 +91,7 @@ impl Region {
             Region::Na => "https://api.worldofwarships.com",
             Region::Eu => "https://api.worldofwarships.eu",
         };
-        Self::_construct_url(base, sub_url)
+        Self::_construct_url(&self._base("api", base), sub_url)
     }
 
     /// player profile url ( https://profile.worldofwarships.asia )
@@ -113,17 +121,44 @@ impl Region {
             Region::Na => "https://clans.worldofwarships.com",
             Region::Eu => "https://clans.worldofwarships.eu",
         };
-        Self::_construct_url(base, sub_url)
+        Self::_construct_url(&self._base("clan", base), sub_url)
     }
 
     /// kokomi url ( http://43.160.202.239:8000/docs )
     pub fn kokomi_url(&self, sub_url: impl AsRef<str>) -> Url {
-        let base = match self {
-            Region::Asia => "http://43.160.202.239:8000",
-            Region::Na => "http://43.165.127.36:8000",
-            Region::Eu => "http://43.165.3.119:8000",
-        };
-        Self::_construct_url(base, sub_url)
+        let base = format!("http://{}:8000", self.kokomi_host());
+        Self::_construct_url(&self._base("kokomi", &base), sub_url)
+    }
+
+    /// the host of [`Region::kokomi_url`], not affected by [`BASE_URL_OVERRIDE`]
+    pub fn kokomi_host(&self) -> &'static str {
+        match self {
+            Region::Asia => "43.160.202.239",
+            Region::Na => "43.165.127.36",
+            Region::Eu => "43.165.3.119",
+        }
+    }
+
+    /// the upstream name of the url redirected by [`BASE_URL_OVERRIDE`], e.g. `vortex`
+    pub fn overridden_upstream(url: &Url) -> Option<String> {
+        let guard = BASE_URL_OVERRIDE.read();
+        let base = guard.as_deref()?.trim_end_matches('/');
+        let rest = url.as_str().strip_prefix(base)?.strip_prefix('/')?;
+        rest.split('/').next().map(str::to_string)
+    }
+
+    /// replace the url with the [`BASE_URL_OVERRIDE`] one if it's set
+    fn _base(&self, upstream: &str, default: &str) -> String {
+        match BASE_URL_OVERRIDE.read().as_deref() {
+            Some(base) => format!("{}/{upstream}/{}", base.trim_end_matches('/'), self.lower()),
+            None => default.to_string(),
+        }
+    }
+
+    /// redirect the upstreams to `base`, or restore them with `None`
+    #[cfg(test)]
+    pub fn override_base_url(base: Option<String>) {
+        *BASE_URL_OVERRIDE.write() = base;
     }
 
     fn _construct_url(base: &str, sub: impl AsRef<str>) -> Url {
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    structs::{PlayerSnapshotsType, ShipStatsCollection},
+    utils::LoadSaveFromJson,
+};
+
+const WEEK: u64 = 7 * 86400;
+
+/// how long the recent snapshots are kept and can be queried, edit the json to change it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// stop tracking a normal player if he didn't use `/recent` in the last X days
+    pub inactive_days: u64,
+    /// snapshots older than X days will be removed, for [`PlayerSnapshotsType::Normal`]
+    pub max_day_normal: u64,
+    /// snapshots older than X days will be removed, for [`PlayerSnapshotsType::Premium`]
+    pub max_day_premium: u64,
+    /// the max `days` a normal user can query
+    pub query_day_normal: u64,
+    /// the max `days` a patron can query
+    pub query_day_patron: u64,
+    /// snapshots older than X days are thinned to one per week, None to keep them daily
+    pub weekly_after_day: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            inactive_days: 14,
+            // 90 days for patrons +1 for the ceiling
+            max_day_normal: 91,
+            max_day_premium: 91,
+            query_day_normal: 30,
+            query_day_patron: 91,
+            weekly_after_day: None,
+        }
+    }
+}
+
+impl LoadSaveFromJson for RetentionPolicy {
+    const PATH: &'static str = "./user_data/retention.json";
+}
+
+impl RetentionPolicy {
+    /// the policy loaded from [`RetentionPolicy::PATH`]
+    pub fn get() -> &'static Self {
+        static POLICY: Lazy<RetentionPolicy> = Lazy::new(RetentionPolicy::load_json_sync);
+        &POLICY
+    }
+
+    /// the max age of the snapshots for the given player type
+    pub fn max_day(&self, player_type: &PlayerSnapshotsType) -> u64 {
+        match player_type {
+            PlayerSnapshotsType::Premium => self.max_day_premium,
+            PlayerSnapshotsType::Normal(_) => self.max_day_normal,
+        }
+    }
+
+    /// the max `days` the user can query
+    pub fn query_day(&self, is_patron: bool) -> u64 {
+        match is_patron {
+            true => self.query_day_patron,
+            false => self.query_day_normal,
+        }
+    }
+
+    /// keep only the oldest snapshot of each week for those older than `weekly_after_day`
+    pub fn thin(&self, now: u64, data: &mut BTreeMap<u64, ShipStatsCollection>) {
+        let Some(weekly_after_day) = self.weekly_after_day else {
+            return;
+        };
+        let threshold = now.saturating_sub(weekly_after_day * 86400);
+        let mut last_week = None;
+        data.retain(|date, _| {
+            if *date >= threshold {
+                return true;
+            }
+            let week = Some(date / WEEK);
+            let keep = week != last_week;
+            last_week = week;
+            keep
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{RetentionPolicy, WEEK};
+    use crate::structs::ShipStatsCollection;
+
+    #[test]
+    fn thin_keeps_one_snapshot_per_week() {
+        let policy = RetentionPolicy {
+            weekly_after_day: Some(7),
+            ..Default::default()
+        };
+        let now = 10 * WEEK;
+        let mut data: BTreeMap<u64, ShipStatsCollection> = (0..70)
+            .map(|day| (day * 86400, ShipStatsCollection::default()))
+            .collect();
+        policy.thin(now, &mut data);
+        // 9 weeks thinned, the last 7 days untouched
+        assert_eq!(data.len(), 9 + 7);
+        assert!(data.contains_key(&0));
+        assert!(!data.contains_key(&86400));
+    }
+}
@@ -166,7 +166,7 @@ impl Display for Ship {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
 pub struct ShipStatsCollection(pub HashMap<ShipId, ShipModeStatsPair>);
 
 impl TryFrom<VortexShipAPIRes> for ShipStatsCollection {
@@ -196,11 +196,11 @@ impl ShipStatsCollection {
     }
 
     /// merging the responses from vortex
-    pub fn merge(mut self, mut other: Self) -> Self {
-        for (ship_id, main_pair) in self.0.iter_mut() {
-            if let Some(sub_pair) = other.0.remove(ship_id) {
-                main_pair.0.extend(sub_pair.0)
-            }
+    ///
+    /// a ship can be missing in some modes' responses, e.g. only played in co-op
+    pub fn merge(mut self, other: Self) -> Self {
+        for (ship_id, sub_pair) in other.0 {
+            self.0.entry(ship_id).or_default().0.extend(sub_pair.0)
         }
         self
     }
@@ -227,18 +227,7 @@ impl ShipStatsCollection {
                 if current.battles_count <= old.battles_count {
                     continue; // no different or account got rollback
                 };
-                let diff_s = ShipStats {
-                    battles_count: current.battles_count - old.battles_count,
-                    wins: current.wins - old.wins,
-                    damage_dealt: current.damage_dealt - old.damage_dealt,
-                    frags: current.frags - old.frags,
-                    planes_killed: current.planes_killed - old.planes_killed,
-                    original_exp: current.original_exp - old.original_exp,
-                    art_agro: current.art_agro - old.art_agro,
-                    scouting_damage: current.scouting_damage - old.scouting_damage,
-                    shots_by_main: current.shots_by_main - old.shots_by_main,
-                    hits_by_main: current.hits_by_main - old.hits_by_main,
-                };
+                let diff_s = current.diff(&old);
                 output_pair.0.insert(mode, diff_s);
             }
             if output_pair.0.is_empty() {
@@ -253,6 +242,83 @@ impl ShipStatsCollection {
         }
     }
 
+    /// the abnormal changes since `older`, which [`Self::compare`] would skip or miscount
+    ///
+    /// `elapsed` is the seconds between the two snapshots
+    pub fn anomalies(&self, older: &Self, elapsed: u64) -> Vec<ShipStatsAnomaly> {
+        let max_battles = MAX_BATTLES_PER_HOUR * elapsed.div_ceil(3600).max(1);
+        let mut anomalies = vec![];
+        for (ship_id, old_pair) in older.0.iter() {
+            let Some(new_pair) = self.0.get(ship_id) else {
+                anomalies.push(ShipStatsAnomaly::Disappeared { ship_id: *ship_id });
+                continue;
+            };
+            if new_pair == old_pair {
+                continue;
+            }
+            for mode in Mode::iter() {
+                let (Some(old), Some(new)) = (old_pair.get(&mode), new_pair.get(&mode)) else {
+                    continue;
+                };
+                let decreased = new
+                    .values()
+                    .into_iter()
+                    .zip(old.values())
+                    .any(|pair| matches!(pair, (Some(new), Some(old)) if new < old));
+                let battles = new.battles_count.saturating_sub(old.battles_count);
+                if decreased {
+                    anomalies.push(ShipStatsAnomaly::Decreased {
+                        ship_id: *ship_id,
+                        mode,
+                    });
+                } else if battles > max_battles {
+                    anomalies.push(ShipStatsAnomaly::ImpossibleJump {
+                        ship_id: *ship_id,
+                        mode,
+                        battles,
+                    });
+                }
+            }
+        }
+        anomalies
+    }
+
+    /// the ships changed or removed since `older`, the inverse of [`Self::apply`]
+    ///
+    /// a changed ship keeps all its stats, so a rollback can be restored as well
+    pub fn delta(&self, older: &Self) -> ShipStatsDelta {
+        ShipStatsDelta {
+            changed: self
+                .0
+                .iter()
+                .filter(|(ship_id, pair)| older.0.get(ship_id) != Some(pair))
+                .map(|(ship_id, pair)| (*ship_id, pair.clone()))
+                .collect(),
+            removed: older
+                .0
+                .keys()
+                .filter(|ship_id| !self.0.contains_key(ship_id))
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// apply the delta on self, turning it into the newer collection
+    pub fn apply(&mut self, delta: ShipStatsDelta) -> &Self {
+        for ship_id in delta.removed {
+            self.0.remove(&ship_id);
+        }
+        self.0.extend(delta.changed);
+        self
+    }
+
+    /// whether any ship has battles in the modes of [`Mode::is_extra`]
+    pub fn has_extra_modes(&self) -> bool {
+        self.0
+            .values()
+            .any(|pair| pair.0.keys().any(Mode::is_extra))
+    }
+
     /// remove those ships doesn't has self in all 4 modes
     pub fn clean(&mut self) -> &Self {
         self.0.retain(|_ship_id, s| !s.0.is_empty());
@@ -300,21 +366,7 @@ impl ShipStatsCollection {
         expected_js: &Arc<RwLock<ExpectedJs>>,
         mode: Mode,
     ) -> Option<Statistic> {
-        let (
-            battles,
-            ttl_wins,
-            ttl_dmg,
-            ttl_frags,
-            ttl_planes,
-            ttl_exp,
-            ttl_potential,
-            ttl_scout,
-            shots,
-            hits,
-            exp_ttl_wins,
-            exp_ttl_dmg,
-            exp_ttl_frags,
-        ) = {
+        let (ttl, exp_ttl_wins, exp_ttl_dmg, exp_ttl_frags) = {
             let guard = expected_js.read();
             let empty_ship_expected = ShipExpected {
                 dmg: 0.0,
@@ -325,28 +377,43 @@ impl ShipStatsCollection {
                 .iter()
                 .filter_map(|(ship_id, ship_modes)| ship_modes.get(&mode).map(|s| (ship_id, s)))
                 .fold(
-                    (0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0.0, 0.0, 0.0),
-                    |acc, (ship_id, ship)| {
+                    (
+                        // Some(0) so the sum stays Some, until a ship is missing them
+                        ShipStats {
+                            survived: Some(0),
+                            ships_spotted: Some(0),
+                            ..Default::default()
+                        },
+                        0.0,
+                        0.0,
+                        0.0,
+                    ),
+                    |mut acc, (ship_id, ship)| {
                         let ship_expected =
                             guard.data.get(&ship_id.0).unwrap_or(&empty_ship_expected); // QA its a reference, so i cant unwrap_or_default()
+                        acc.0.add(ship);
                         (
-                            acc.0 + ship.battles_count,
-                            acc.1 + ship.wins,
-                            acc.2 + ship.damage_dealt,
-                            acc.3 + ship.frags,
-                            acc.4 + ship.planes_killed,
-                            acc.5 + ship.original_exp,
-                            acc.6 + ship.art_agro,
-                            acc.7 + ship.scouting_damage,
-                            acc.8 + ship.shots_by_main,
-                            acc.9 + ship.hits_by_main,
-                            acc.10 + ship_expected.winrate / 100.0 * ship.battles_count as f64,
-                            acc.11 + ship_expected.dmg * ship.battles_count as f64,
-                            acc.12 + ship_expected.frags * ship.battles_count as f64,
+                            acc.0,
+                            acc.1 + ship_expected.winrate / 100.0 * ship.battles_count as f64,
+                            acc.2 + ship_expected.dmg * ship.battles_count as f64,
+                            acc.3 + ship_expected.frags * ship.battles_count as f64,
                         )
                     },
                 )
         };
+        let ShipStats {
+            battles_count: battles,
+            wins: ttl_wins,
+            damage_dealt: ttl_dmg,
+            frags: ttl_frags,
+            planes_killed: ttl_planes,
+            original_exp: ttl_exp,
+            art_agro: ttl_potential,
+            scouting_damage: ttl_scout,
+            shots_by_main: shots,
+            hits_by_main: hits,
+            ..
+        } = ttl;
         if battles == 0 {
             return None;
         };
@@ -398,10 +465,25 @@ impl ShipStatsCollection {
             potential,
             scout,
             hitrate,
+            survival: ttl.survival(),
+            kd: ttl.kd(),
+            spotted: ttl.spotted(),
+            max_dmg: ttl.max_damage_dealt,
+            max_frags: ttl.max_frags,
+            max_exp: ttl.max_exp,
         })
     }
 }
 
+/// the difference between two [`ShipStatsCollection`], see [`ShipStatsCollection::delta`]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ShipStatsDelta {
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub changed: HashMap<ShipId, ShipModeStatsPair>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<ShipId>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq, Default)]
 pub struct ShipId(pub u64);
 impl ShipId {
@@ -453,6 +535,32 @@ impl ShipModeStatsPair {
     fn get(&self, mode: &Mode) -> Option<&ShipStats> {
         self.0.get(mode)
     }
+
+    /// move this older snapshot by `current - latest`, so the diff against `current` only skips
+    /// the battles between `latest` and `current`, see [`crate::structs::PlayerSnapshots::rebaseline`]
+    ///
+    /// None if it can't be moved, e.g. a mode is gone or the rollback is bigger than the battles since then
+    pub fn shifted(&self, latest: &Self, current: &Self) -> Option<Self> {
+        if self
+            .0
+            .keys()
+            .chain(latest.0.keys())
+            .any(|mode| current.get(mode).is_none())
+        {
+            return None;
+        }
+        let default = ShipStats::default();
+        current
+            .0
+            .iter()
+            .map(|(mode, current)| {
+                let old = self.get(mode).unwrap_or(&default);
+                let latest = latest.get(mode).unwrap_or(&default);
+                Some((*mode, old.shifted(latest, current)?))
+            })
+            .collect::<Option<_>>()
+            .map(Self)
+    }
     /// calculate the statistic of the ship, None if battles = 0
     pub fn to_statistic(
         &self,
@@ -498,6 +606,12 @@ impl ShipModeStatsPair {
             potential: potential.round() as u64,
             scout: scout.round() as u64,
             hitrate,
+            survival: stats.survival(),
+            kd: stats.kd(),
+            spotted: stats.spotted(),
+            max_dmg: stats.max_damage_dealt,
+            max_frags: stats.max_frags,
+            max_exp: stats.max_exp,
         })
     }
 }
@@ -536,10 +650,37 @@ impl TryFrom<JsonValue> for ShipModeStatsPair {
     }
 }
 
+/// more battles than this in an hour are treated as [`ShipStatsAnomaly::ImpossibleJump`]
+const MAX_BATTLES_PER_HOUR: u64 = 30;
+
+/// the abnormal changes between two snapshots, see [`ShipStatsCollection::anomalies`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShipStatsAnomaly {
+    /// some stats got lower, e.g. account rollback or stats reset
+    Decreased { ship_id: ShipId, mode: Mode },
+    /// the ship was in the older snapshot but not in the newer one
+    Disappeared { ship_id: ShipId },
+    /// too many battles for the elapsed time, e.g. stats merged
+    ImpossibleJump {
+        ship_id: ShipId,
+        mode: Mode,
+        battles: u64,
+    },
+}
+
+impl ShipStatsAnomaly {
+    pub fn ship_id(&self) -> ShipId {
+        match self {
+            Self::Decreased { ship_id, .. }
+            | Self::Disappeared { ship_id }
+            | Self::ImpossibleJump { ship_id, .. } => *ship_id,
+        }
+    }
+}
+
 /// the battles_count should never be 0
 #[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
 pub struct ShipStats {
-    #[serde(alias = "battles")] // for read old recent data
     battles_count: u64,
     wins: u64,
     damage_dealt: u64,
@@ -551,6 +692,180 @@ pub struct ShipStats {
     scouting_damage: u64,
     shots_by_main: u64,
     hits_by_main: u64,
+    // the fields below are missing in the old recent data, None there so the diff can skip them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    survived: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ships_spotted: Option<u64>,
+    #[serde(default)]
+    max_damage_dealt: u64,
+    #[serde(default)]
+    max_frags: u64,
+    #[serde(default)]
+    max_exp: u64,
+}
+
+impl ShipStats {
+    /// the field names in the order of [`ShipStats::values`], for exporting
+    pub const FIELDS: [&'static str; 15] = [
+        "battles_count",
+        "wins",
+        "damage_dealt",
+        "frags",
+        "planes_killed",
+        "original_exp",
+        "art_agro",
+        "scouting_damage",
+        "shots_by_main",
+        "hits_by_main",
+        "survived",
+        "ships_spotted",
+        "max_damage_dealt",
+        "max_frags",
+        "max_exp",
+    ];
+
+    /// the raw numbers, see [`ShipStats::FIELDS`], None if the old data doesn't have it
+    pub fn values(&self) -> [Option<u64>; 15] {
+        [
+            Some(self.battles_count),
+            Some(self.wins),
+            Some(self.damage_dealt),
+            Some(self.frags),
+            Some(self.planes_killed),
+            Some(self.original_exp),
+            Some(self.art_agro),
+            Some(self.scouting_damage),
+            Some(self.shots_by_main),
+            Some(self.hits_by_main),
+            self.survived,
+            self.ships_spotted,
+            Some(self.max_damage_dealt),
+            Some(self.max_frags),
+            Some(self.max_exp),
+        ]
+    }
+
+    /// the stats gained since `old`, the max records are kept instead of subtracted, so they are lifetime records
+    ///
+    /// the stats `old` doesn't have are None, instead of counting the whole lifetime as gained
+    ///
+    /// **Note**: `self` need to be later, and have more battles than `old`
+    fn diff(&self, old: &Self) -> Self {
+        Self {
+            battles_count: self.battles_count - old.battles_count,
+            wins: self.wins - old.wins,
+            damage_dealt: self.damage_dealt - old.damage_dealt,
+            frags: self.frags - old.frags,
+            planes_killed: self.planes_killed - old.planes_killed,
+            original_exp: self.original_exp - old.original_exp,
+            art_agro: self.art_agro - old.art_agro,
+            scouting_damage: self.scouting_damage - old.scouting_damage,
+            shots_by_main: self.shots_by_main - old.shots_by_main,
+            hits_by_main: self.hits_by_main - old.hits_by_main,
+            // the old data might not have these
+            survived: self.survived.zip(old.survived).map(|(new, old)| new - old),
+            ships_spotted: self
+                .ships_spotted
+                .zip(old.ships_spotted)
+                .map(|(new, old)| new - old),
+            max_damage_dealt: self.max_damage_dealt.max(old.max_damage_dealt),
+            max_frags: self.max_frags.max(old.max_frags),
+            max_exp: self.max_exp.max(old.max_exp),
+        }
+    }
+
+    /// `self + current - latest` for the counters, None if any of them gets negative
+    ///
+    /// the max records are taken from `current`, they are lifetime records anyway
+    fn shifted(&self, latest: &Self, current: &Self) -> Option<Self> {
+        let shift = |old: u64, latest: u64, current: u64| (old + current).checked_sub(latest);
+        let shift_opt = |old: Option<u64>, latest: Option<u64>, current: Option<u64>| match (
+            old, latest, current,
+        ) {
+            (Some(old), Some(latest), Some(current)) => shift(old, latest, current).map(Some),
+            _ => Some(None),
+        };
+        Some(Self {
+            battles_count: shift(
+                self.battles_count,
+                latest.battles_count,
+                current.battles_count,
+            )?,
+            wins: shift(self.wins, latest.wins, current.wins)?,
+            damage_dealt: shift(self.damage_dealt, latest.damage_dealt, current.damage_dealt)?,
+            frags: shift(self.frags, latest.frags, current.frags)?,
+            planes_killed: shift(
+                self.planes_killed,
+                latest.planes_killed,
+                current.planes_killed,
+            )?,
+            original_exp: shift(self.original_exp, latest.original_exp, current.original_exp)?,
+            art_agro: shift(self.art_agro, latest.art_agro, current.art_agro)?,
+            scouting_damage: shift(
+                self.scouting_damage,
+                latest.scouting_damage,
+                current.scouting_damage,
+            )?,
+            shots_by_main: shift(
+                self.shots_by_main,
+                latest.shots_by_main,
+                current.shots_by_main,
+            )?,
+            hits_by_main: shift(self.hits_by_main, latest.hits_by_main, current.hits_by_main)?,
+            survived: shift_opt(self.survived, latest.survived, current.survived)?,
+            ships_spotted: shift_opt(
+                self.ships_spotted,
+                latest.ships_spotted,
+                current.ships_spotted,
+            )?,
+            max_damage_dealt: current.max_damage_dealt,
+            max_frags: current.max_frags,
+            max_exp: current.max_exp,
+        })
+    }
+
+    /// sum up with `other`, the max records are kept instead of added
+    ///
+    /// a stat missing in either one is None, a partial sum would be wrong
+    fn add(&mut self, other: &Self) {
+        self.battles_count += other.battles_count;
+        self.wins += other.wins;
+        self.damage_dealt += other.damage_dealt;
+        self.frags += other.frags;
+        self.planes_killed += other.planes_killed;
+        self.original_exp += other.original_exp;
+        self.art_agro += other.art_agro;
+        self.scouting_damage += other.scouting_damage;
+        self.shots_by_main += other.shots_by_main;
+        self.hits_by_main += other.hits_by_main;
+        self.survived = self.survived.zip(other.survived).map(|(a, b)| a + b);
+        self.ships_spotted = self
+            .ships_spotted
+            .zip(other.ships_spotted)
+            .map(|(a, b)| a + b);
+        self.max_damage_dealt = self.max_damage_dealt.max(other.max_damage_dealt);
+        self.max_frags = self.max_frags.max(other.max_frags);
+        self.max_exp = self.max_exp.max(other.max_exp);
+    }
+
+    /// survival rate in percentage, two decimal places
+    fn survival(&self) -> Option<f64> {
+        let survived = self.survived?;
+        Some((survived as f64 / self.battles_count as f64 * 10000.0).round() / 100.0)
+    }
+
+    /// frags per death, two decimal places, the frags if never died
+    fn kd(&self) -> Option<f64> {
+        let deaths = self.battles_count.saturating_sub(self.survived?).max(1);
+        Some((self.frags as f64 / deaths as f64 * 100.0).round() / 100.0)
+    }
+
+    /// ships spotted per battle, two decimal places
+    fn spotted(&self) -> Option<f64> {
+        let spotted = self.ships_spotted?;
+        Some((spotted as f64 / self.battles_count as f64 * 100.0).round() / 100.0)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -569,16 +884,21 @@ pub struct VortexShipAPIRes {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
+    use parking_lot::RwLock;
     use tracing::error;
 
     use super::{ShipStatsCollection, VortexShipAPIRes};
+    use crate::{
+        structs::{ExpectedJs, Mode, Region},
+        utils::mock_upstream,
+    };
 
     #[tokio::test]
     async fn ship_stats_collection_hidden_can_deserialize() {
-        let client = reqwest::Client::new();
-        let res = client
-            .get("https://vortex.worldofwarships.asia/api/accounts/2008493987/ships/")
-            .send()
+        mock_upstream::start();
+        let res = reqwest::get(Region::Asia.vortex_url("/api/accounts/1002/ships/pvp/"))
             .await
             .unwrap()
             .json::<ShipStatsCollection>()
@@ -593,16 +913,86 @@ mod test {
 
     #[tokio::test]
     async fn vortex_ship_response_can_deserialize() {
-        let client = reqwest::Client::new();
-        let _response = client
-            .get(
-                "https://vortex.worldofwarships.asia/api/accounts/2025455227/ships/3530504176/pvp/",
-            )
-            .send()
-            .await
-            .unwrap()
-            .json::<VortexShipAPIRes>()
-            .await
-            .unwrap();
+        mock_upstream::start();
+        let _response =
+            reqwest::get(Region::Asia.vortex_url("/api/accounts/1001/ships/3530504176/pvp/"))
+                .await
+                .unwrap()
+                .json::<VortexShipAPIRes>()
+                .await
+                .unwrap();
+    }
+
+    #[test]
+    fn compare_keeps_max_records() {
+        let old: ShipStatsCollection = serde_json::from_str(
+            r#"{"4277090288":{"pvp":{"battles_count":10,"wins":5,"damage_dealt":500,"frags":10,"planes_killed":0,"original_exp":10,"scouting_damage":0,"shots_by_main":0,"hits_by_main":0,"survived":4,"max_damage_dealt":200,"max_frags":3}}}"#,
+        )
+        .unwrap();
+        let new: ShipStatsCollection = serde_json::from_str(
+            r#"{"4277090288":{"pvp":{"battles_count":14,"wins":7,"damage_dealt":900,"frags":16,"planes_killed":0,"original_exp":14,"scouting_damage":0,"shots_by_main":0,"hits_by_main":0,"survived":6,"max_damage_dealt":250,"max_frags":3}}}"#,
+        )
+        .unwrap();
+        let diff = new.compare(old).unwrap();
+        let stats = diff.0.values().next().unwrap().get(&Mode::Pvp).unwrap();
+        // counters are subtracted, records are kept
+        assert_eq!(stats.battles_count, 4);
+        assert_eq!(stats.survived, Some(2));
+        assert_eq!(stats.max_damage_dealt, 250);
+        assert_eq!(stats.max_frags, 3);
+        assert_eq!(stats.survival(), Some(50.0));
+        assert_eq!(stats.kd(), Some(3.0));
+        // the old data has no `ships_spotted`
+        assert_eq!(stats.spotted(), None);
+    }
+
+    #[test]
+    fn compare_skips_stats_missing_in_old_data() {
+        let old: ShipStatsCollection = serde_json::from_str(
+            r#"{"4277090288":{"pvp":{"battles_count":1000,"wins":500,"damage_dealt":500,"frags":10,"planes_killed":0,"original_exp":10,"scouting_damage":0,"shots_by_main":0,"hits_by_main":0}}}"#,
+        )
+        .unwrap();
+        let new: ShipStatsCollection = serde_json::from_str(
+            r#"{"4277090288":{"pvp":{"battles_count":1002,"wins":501,"damage_dealt":900,"frags":12,"planes_killed":0,"original_exp":14,"scouting_damage":0,"shots_by_main":0,"hits_by_main":0,"survived":400,"ships_spotted":900}}}"#,
+        )
+        .unwrap();
+        assert!(new.anomalies(&old, 3600).is_empty());
+        let diff = new.compare(old).unwrap();
+        let stats = diff.0.values().next().unwrap().get(&Mode::Pvp).unwrap();
+        // not the lifetime 400 survived in 2 battles
+        assert_eq!(
+            (stats.survival(), stats.kd(), stats.spotted()),
+            (None, None, None)
+        );
+    }
+
+    #[test]
+    fn to_statistic_sums_survival_and_spotted() {
+        let ships: ShipStatsCollection = serde_json::from_str(
+            r#"{
+            "4277090288":{"pvp":{"battles_count":10,"wins":5,"damage_dealt":500,"frags":10,"planes_killed":0,"original_exp":10,"scouting_damage":0,"shots_by_main":0,"hits_by_main":0,"survived":4,"ships_spotted":10}},
+            "3530504176":{"pvp":{"battles_count":10,"wins":5,"damage_dealt":500,"frags":6,"planes_killed":0,"original_exp":10,"scouting_damage":0,"shots_by_main":0,"hits_by_main":0,"survived":8,"ships_spotted":20}}
+        }"#,
+        )
+        .unwrap();
+        let expected = Arc::new(RwLock::new(ExpectedJs::default()));
+        let stats = ships.to_statistic(&expected, Mode::Pvp).unwrap();
+        // 12 of 20 survived, 16 frags in 8 deaths, 30 spotted in 20 battles
+        assert_eq!(stats.survival, Some(60.0));
+        assert_eq!(stats.kd, Some(2.0));
+        assert_eq!(stats.spotted, Some(1.5));
+
+        // one ship from the old data, the sum would be wrong
+        let mut mixed = ships.clone();
+        let old: ShipStatsCollection = serde_json::from_str(
+            r#"{"4277090288":{"pvp":{"battles_count":10,"wins":5,"damage_dealt":500,"frags":10,"planes_killed":0,"original_exp":10,"scouting_damage":0,"shots_by_main":0,"hits_by_main":0}}}"#,
+        )
+        .unwrap();
+        mixed.0.extend(old.0);
+        let stats = mixed.to_statistic(&expected, Mode::Pvp).unwrap();
+        assert_eq!(
+            (stats.survival, stats.kd, stats.spotted),
+            (None, None, None)
+        );
     }
 }
@@ -16,6 +16,34 @@ pub struct Statistic {
     pub potential: u64,
     pub scout: u64,
     pub hitrate: f64,
+    /// None if the old recent data doesn't have it, rendered as `-`
+    #[serde(serialize_with = "percent_or_dash", skip_deserializing)]
+    pub survival: Option<f64>,
+    #[serde(serialize_with = "number_or_dash", skip_deserializing)]
+    pub kd: Option<f64>,
+    #[serde(serialize_with = "number_or_dash", skip_deserializing)]
+    pub spotted: Option<f64>,
+    /// lifetime records, even in the stats of a period
+    #[serde(default)]
+    pub max_dmg: u64,
+    #[serde(default)]
+    pub max_frags: u64,
+    #[serde(default)]
+    pub max_exp: u64,
+}
+
+fn number_or_dash<S: serde::Serializer>(value: &Option<f64>, s: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => s.serialize_f64(*value),
+        None => s.serialize_str("-"),
+    }
+}
+
+fn percent_or_dash<S: serde::Serializer>(value: &Option<f64>, s: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => s.serialize_str(&format!("{value}%")),
+        None => s.serialize_str("-"),
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
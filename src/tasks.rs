@@ -7,5 +7,14 @@ pub use expected::expected_updater;
 mod ships_para;
 pub use ships_para::ships_para_updater;
 
+mod snapshot_updater;
+pub use snapshot_updater::snapshot_updater;
+
+mod health_monitor;
+pub use health_monitor::health_monitor;
+
+mod metrics_server;
+pub use metrics_server::metrics_server;
+
 mod browser;
 pub use browser::*;
@@ -12,13 +12,196 @@
 //         .expect("Failed to start subprocess");
 // }
 
-use std::env;
+use std::{
+    env,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
 
-pub async fn launch_renderer() -> tokio::process::Child {
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::Client;
+use tokio::{process::Child, sync::mpsc::UnboundedSender};
+use tracing::{error, info, warn};
+
+use crate::{
+    template_data::{RENDER_POOL, RenderWorker},
+    utils::metrics::METRICS,
+};
+
+/// how often the renderers are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+/// the failed checks in a row before restarting a renderer that was ready
+const MAX_FAILURES: u32 = 3;
+/// restart the renderer if it's not ready by then, launching chromium is slow on a small VPS
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(90);
+const MIN_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// a renderer that lived this long is restarted without backing off
+const STABLE_AFTER: Duration = Duration::from_secs(600);
+
+/// the pids of the running local renderers
+static PIDS: Lazy<Mutex<Vec<u32>>> = Lazy::new(Mutex::default);
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+/// wait until a renderer is ready, false if none is after the timeout
+pub async fn wait_renderer_ready(timeout: Duration) -> bool {
+    RENDER_POOL.wait_ready(timeout).await
+}
+
+/// stop the local renderers without restarting them, for shutting down the bot
+pub fn stop_renderer() {
+    STOPPING.store(true, Ordering::SeqCst);
+    #[cfg(target_os = "linux")]
+    for pid in PIDS.lock().iter() {
+        unsafe { libc::kill(*pid as i32, libc::SIGTERM) };
+    }
+}
+
+fn launch_renderer(port: u16) -> std::io::Result<Child> {
     let python = env::var("PYTHON_PATH").unwrap_or("python".to_string());
-    // Replace "path/to/executable" with the actual path to your executable
     tokio::process::Command::new(python)
         .arg("./renderer/renderer.py")
+        .arg(format!("--port={port}"))
+        .kill_on_drop(true)
         .spawn()
-        .unwrap_or_else(|err| panic!("Failed to start subprocess: {}", err))
+}
+
+/// health check all the renderer workers, the local ones are launched and restarted with backoff
+pub async fn renderer_supervisor(client: Client, webhook_tx: UnboundedSender<String>) {
+    let supervisors = RENDER_POOL
+        .workers
+        .iter()
+        .enumerate()
+        .map(|(index, worker)| match worker.local_port() {
+            Some(port) => tokio::spawn(supervise_local(
+                client.clone(),
+                webhook_tx.clone(),
+                index,
+                port,
+            )),
+            None => tokio::spawn(supervise_remote(client.clone(), webhook_tx.clone(), index)),
+        });
+    futures::future::join_all(supervisors).await;
+}
+
+async fn supervise_local(
+    client: Client,
+    webhook_tx: UnboundedSender<String>,
+    index: usize,
+    port: u16,
+) {
+    let worker = &RENDER_POOL.workers[index];
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let launched = Instant::now();
+        let reason = match launch_renderer(port) {
+            Ok(mut child) => {
+                let pid = child.id().unwrap_or_default();
+                PIDS.lock().push(pid);
+                let reason = watch_local(&client, index, &mut child).await;
+                RENDER_POOL.set_ready(index, false);
+                PIDS.lock().retain(|p| *p != pid);
+                if STOPPING.load(Ordering::SeqCst) {
+                    return;
+                }
+                let _ = child.kill().await;
+                reason
+            }
+            Err(err) => format!("failed to launch: {err}"),
+        };
+        if launched.elapsed() > STABLE_AFTER {
+            backoff = MIN_BACKOFF;
+        }
+        METRICS.inc(
+            "isac_renderer_restarts_total",
+            &[("worker", worker.url.as_str())],
+        );
+        let msg = format!(
+            "Renderer {} {reason}, restarting in {}s",
+            worker.url,
+            backoff.as_secs()
+        );
+        error!("{msg}");
+        let _ = webhook_tx.send(format!("🔄 {msg}"));
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// health check a local renderer until it should be restarted, returns why
+async fn watch_local(client: &Client, index: usize, child: &mut Child) -> String {
+    let worker = &RENDER_POOL.workers[index];
+    let launched = Instant::now();
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    let mut failures = 0;
+    loop {
+        tokio::select! {
+            status = child.wait() => {
+                return match status {
+                    Ok(status) => format!("exited with {status}"),
+                    Err(err) => format!("failed to wait: {err}"),
+                };
+            }
+            _ = interval.tick() => (),
+        }
+        let ok = check(client, worker).await;
+        match (ok, RENDER_POOL.worker_ready(index)) {
+            (true, false) => {
+                info!(
+                    "Renderer {} is ready after {}s",
+                    worker.url,
+                    launched.elapsed().as_secs()
+                );
+                failures = 0;
+                RENDER_POOL.set_ready(index, true);
+            }
+            (true, true) => failures = 0,
+            (false, true) => {
+                failures += 1;
+                warn!(
+                    "Renderer {} health check failed ({failures}/{MAX_FAILURES})",
+                    worker.url
+                );
+                if failures >= MAX_FAILURES {
+                    return "stopped responding".to_string();
+                }
+            }
+            (false, false) if launched.elapsed() > LAUNCH_TIMEOUT => {
+                return format!("not ready after {}s", LAUNCH_TIMEOUT.as_secs());
+            }
+            (false, false) => (),
+        }
+    }
+}
+
+/// a renderer on another machine, it's only taken out of the pool while it's down
+async fn supervise_remote(client: Client, webhook_tx: UnboundedSender<String>, index: usize) {
+    let worker = &RENDER_POOL.workers[index];
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    let mut failures = 0;
+    loop {
+        interval.tick().await;
+        let ok = check(&client, worker).await;
+        failures = if ok { 0 } else { failures + 1 };
+        let ready = RENDER_POOL.worker_ready(index);
+        if ok && !ready {
+            info!("Renderer {} is ready", worker.url);
+            RENDER_POOL.set_ready(index, true);
+        } else if ready && failures >= MAX_FAILURES {
+            error!("Renderer {} stopped responding", worker.url);
+            let _ = webhook_tx.send(format!("🔄 Renderer {} stopped responding", worker.url));
+            RENDER_POOL.set_ready(index, false);
+        }
+    }
+}
+
+async fn check(client: &Client, worker: &RenderWorker) -> bool {
+    client
+        .get(worker.health_url())
+        .timeout(CHECK_TIMEOUT)
+        .send()
+        .await
+        .is_ok_and(|res| res.status().is_success())
 }
@@ -1,10 +1,17 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use parking_lot::RwLock;
-use reqwest::Client;
+use reqwest::{Client, Url};
 use tokio::sync::mpsc::UnboundedSender;
 
-use crate::{LoadSaveFromJson, structs::ExpectedJs};
+use crate::{
+    LoadSaveFromJson,
+    structs::ExpectedJs,
+    utils::{metrics::METRICS, request_policy::RequestPolicy},
+};
 
 pub async fn expected_updater(
     client: Client,
@@ -14,7 +21,10 @@ pub async fn expected_updater(
     let mut interval = tokio::time::interval(Duration::from_secs(86400));
     loop {
         interval.tick().await;
-        match request(&client).await {
+        let started = Instant::now();
+        let res = request(&client).await;
+        METRICS.task_run("expected_updater", res.is_ok(), started.elapsed());
+        match res {
             Ok(expected_js) => {
                 expected_js.save_json().await;
                 *expected_arc.write() = expected_js;
@@ -27,10 +37,8 @@ pub async fn expected_updater(
 }
 
 async fn request(client: &Client) -> Result<ExpectedJs, reqwest::Error> {
-    client
-        .get("https://api.wows-numbers.com/personal/rating/expected/json/")
-        .send()
-        .await?
-        .json::<ExpectedJs>()
-        .await
+    let url = Url::parse("https://api.wows-numbers.com/personal/rating/expected/json/").unwrap();
+    // not through WowsApi, but still share its rate limit of wows-numbers
+    RequestPolicy::throttle(&url).await;
+    client.get(url).send().await?.json::<ExpectedJs>().await
 }
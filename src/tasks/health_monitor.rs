@@ -0,0 +1,47 @@
+use std::{collections::HashSet, time::Duration};
+
+use tokio::{sync::mpsc::UnboundedSender, time::Instant};
+use tracing::{info, warn};
+
+use crate::{
+    Data,
+    utils::{
+        metrics::METRICS,
+        upstream_health::{Health, UPSTREAM_HEALTH},
+        wws_api::WowsApi,
+    },
+};
+
+/// probe all the upstreams every 5 minutes, see [`UPSTREAM_HEALTH`]
+///
+/// only the outages and their recoveries are sent to the webhook, not the flapping
+pub async fn health_monitor(data: Data, webhook_tx: UnboundedSender<String>) {
+    // give the renderer some time to launch
+    let mut interval = tokio::time::interval_at(
+        Instant::now() + Duration::from_secs(30),
+        Duration::from_secs(300),
+    );
+    // the services notified as down, and not recovered yet
+    let mut down = HashSet::new();
+    loop {
+        interval.tick().await;
+        let api = WowsApi::from_data(&data);
+        let started = std::time::Instant::now();
+        let changed = UPSTREAM_HEALTH.probe_all(&api).await;
+        METRICS.task_run("health_monitor", true, started.elapsed());
+        for (service, health) in changed {
+            match health {
+                Health::Down | Health::Degraded => warn!("{service} is {health} now"),
+                Health::Up | Health::Unknown => info!("{service} is {health} now"),
+            }
+            // `Up` needs a clean window, the first good probe after an outage is `Degraded`
+            if health == Health::Down {
+                if down.insert(service) {
+                    let _ = webhook_tx.send(format!("{} {service} is {health}", health.emoji()));
+                }
+            } else if down.remove(&service) {
+                let _ = webhook_tx.send(format!("{} {service} is back", Health::Up.emoji()));
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::env;
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{info, warn};
+
+use crate::utils::metrics::METRICS;
+
+/// serve [`METRICS`] at `http://{METRICS_ADDR}/metrics` for Prometheus to scrape
+pub async fn metrics_server() {
+    let addr = env::var("METRICS_ADDR").unwrap_or("127.0.0.1:9464".to_string());
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            warn!("Failed to bind metrics server on {addr}, err: {err}");
+            return;
+        }
+    };
+    info!("Serving metrics on http://{addr}/metrics");
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(serve(stream));
+            }
+            Err(err) => warn!("metrics server accept failed, err: {err}"),
+        }
+    }
+}
+
+async fn serve(mut stream: TcpStream) {
+    let mut request_line = String::new();
+    if BufReader::new(&mut stream)
+        .read_line(&mut request_line)
+        .await
+        .is_err()
+    {
+        return;
+    }
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = match path.split('?').next() {
+        Some("/metrics") => ("200 OK", METRICS.render()),
+        _ => ("404 Not Found", "Not Found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
@@ -1,7 +1,7 @@
 use std::{
     env::{self, VarError},
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use once_cell::sync::Lazy;
@@ -13,7 +13,7 @@ use tracing::warn;
 use crate::{
     Error,
     structs::{Linked, Patron, Patrons},
-    utils::LoadSaveFromJson,
+    utils::{LoadSaveFromJson, metrics::METRICS},
 };
 
 pub async fn patron_updater(
@@ -29,7 +29,10 @@ pub async fn patron_updater(
         let mut fail_count = 0;
         loop {
             interval.tick().await;
-            match get_patrons(&http, IDS.as_ref().unwrap()).await {
+            let started = Instant::now();
+            let res = get_patrons(&http, IDS.as_ref().unwrap()).await;
+            METRICS.task_run("patron_updater", res.is_ok(), started.elapsed());
+            match res {
                 Ok(patrons) => {
                     patrons.save_json().await;
                     *patrons_arc.write() = patrons;
@@ -1,13 +1,17 @@
-use std::{error::Error, sync::Arc, time::Duration};
+use std::{
+    error::Error,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use parking_lot::RwLock;
-use reqwest::Client;
+use reqwest::{Client, Url};
 use serde_json::Value;
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     structs::{ShipsPara, VortexVehicleAPIRes},
-    utils::{IsacError, LoadSaveFromJson},
+    utils::{IsacError, LoadSaveFromJson, metrics::METRICS, request_policy::RequestPolicy},
 };
 
 pub async fn ships_para_updater(
@@ -19,7 +23,10 @@ pub async fn ships_para_updater(
     let mut last_ship_count = ships_arc.read().0.len();
     loop {
         interval.tick().await;
-        let new_ships_para = match encyclopedia_vehicles(&client).await {
+        let started = Instant::now();
+        let res = encyclopedia_vehicles(&client).await;
+        METRICS.task_run("ships_para_updater", res.is_ok(), started.elapsed());
+        let new_ships_para = match res {
             Ok(new_ships_para) => new_ships_para,
             Err(err) => {
                 let _ = webhook_tx.send(format!("Update ships para failed!, err: \n{err}"));
@@ -47,8 +54,12 @@ pub async fn ships_para_updater(
     }
 }
 async fn encyclopedia_vehicles(client: &Client) -> Result<ShipsPara, IsacError> {
+    let url =
+        Url::parse("https://vortex.worldofwarships.com/api/encyclopedia/en/vehicles/").unwrap();
+    // not through WowsApi, but still share its rate limit of vortex
+    RequestPolicy::throttle(&url).await;
     client
-        .get("https://vortex.worldofwarships.com/api/encyclopedia/en/vehicles/")
+        .get(url)
         .send()
         .await?
         .json::<VortexVehicleAPIRes>()
@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chrono::Utc;
+use futures::{StreamExt, future::join_all};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::info;
+
+use crate::{
+    Data,
+    structs::{PartialPlayer, PlayerSnapshots, Region},
+    utils::{IsacError, IsacInfo, metrics::METRICS, wws_api::WowsApi},
+};
+
+/// how many players are updated at the same time
+const CONCURRENT_LIMIT: usize = 10;
+
+/// update the `/recent` snapshots of all tracked players once a day, each region has its own schedule
+pub async fn snapshot_updater(data: Data, webhook_tx: UnboundedSender<String>) {
+    join_all(
+        [Region::Asia, Region::Na, Region::Eu]
+            .into_iter()
+            .map(|region| region_updater(&data, region, &webhook_tx)),
+    )
+    .await;
+}
+
+async fn region_updater(data: &Data, region: Region, webhook_tx: &UnboundedSender<String>) {
+    loop {
+        tokio::time::sleep(until_next(update_hour(region))).await;
+        info!("{region} snapshots update start");
+        let start = Instant::now();
+        let (updated, errors) = update(data, region).await;
+        METRICS.task_run(
+            &format!("snapshot_updater_{}", region.lower()),
+            errors.is_empty(),
+            start.elapsed(),
+        );
+        info!(
+            "{region} snapshots updated: {updated}, failed: {}, took time: {}s",
+            errors.len(),
+            start.elapsed().as_secs()
+        );
+        if let Some(err) = errors.first() {
+            let _ = webhook_tx.send(format!(
+                "{region} snapshots updating: {} failed!, err: \n{err}",
+                errors.len()
+            ));
+        }
+    }
+}
+
+/// update all players in the region, return the updated count and the errors
+async fn update(data: &Data, region: Region) -> (usize, Vec<IsacError>) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let api = WowsApi::from_data(data);
+    let api_ref = &api;
+    let results = futures::stream::iter(PlayerSnapshots::players(region).await)
+        .map(|player| async move { update_player(api_ref, player, now).await })
+        .buffer_unordered(CONCURRENT_LIMIT)
+        .collect::<Vec<_>>()
+        .await;
+    let updated = results.iter().filter(|r| r.is_ok()).count();
+    let errors = results.into_iter().filter_map(Result::err).collect();
+    (updated, errors)
+}
+
+/// fetch the player's current ships if he is still active, and drop the outdated snapshots
+async fn update_player(
+    api: &WowsApi<'_>,
+    player: PartialPlayer,
+    now: u64,
+) -> Result<(), IsacError> {
+    let Some(snapshots) = PlayerSnapshots::load(player).await? else {
+        return Ok(());
+    };
+    let current_ships = if snapshots.is_active(now) {
+        match player.all_ships(api, snapshots.tracks_extra_modes()).await {
+            Ok(ships) => Some(ships).filter(|ships| !ships.0.is_empty()),
+            // keep his data, maybe he will open it again
+            Err(IsacError::Info(IsacInfo::PlayerHidden { .. })) => return Ok(()),
+            Err(err) => Err(err)?,
+        }
+    } else {
+        None
+    };
+    // not locked while fetching, so reload in case a command saved him in the meantime
+    let _guard = PlayerSnapshots::lock(player).await;
+    let Some(mut snapshots) = PlayerSnapshots::load(player).await? else {
+        return Ok(());
+    };
+    snapshots.prune(now, current_ships.as_ref());
+    if let Some(current_ships) = current_ships {
+        let anomalies = snapshots.rebaseline(now, &current_ships);
+        if !anomalies.is_empty() {
+            info!("Rebaselined {player:?} for anomalies: {anomalies:?}");
+        }
+        snapshots.insert(current_ships);
+    }
+
+    // an open `/session` is kept even if he's inactive
+    if snapshots.data.is_empty() && snapshots.session.is_none() {
+        snapshots.delete().await?;
+    } else {
+        snapshots.save().await?;
+    }
+    Ok(())
+}
+
+/// the UTC hour to update the region, after its daily server restart
+fn update_hour(region: Region) -> u32 {
+    match region {
+        Region::Asia => 21,
+        Region::Na => 10,
+        Region::Eu => 5,
+    }
+}
+
+/// the duration until the next given UTC hour
+fn until_next(hour: u32) -> Duration {
+    let now = Utc::now();
+    let mut next = now
+        .date_naive()
+        .and_hms_opt(hour, 0, 0)
+        .expect("hour should be valid")
+        .and_utc();
+    if next <= now {
+        next += chrono::Duration::days(1);
+    }
+    (next - now).to_std().unwrap_or_default()
+}
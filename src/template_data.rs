@@ -1,5 +1,13 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
 use bytes::Bytes;
-use reqwest::Client;
+use once_cell::sync::Lazy;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 mod overall;
 pub use overall::*;
@@ -20,26 +28,155 @@ pub use overall_cw::*;
 mod server_top;
 pub use server_top::*;
 
-use crate::utils::{IsacError, IsacInfo};
+mod native;
+mod pool;
+pub use pool::{RENDER_POOL, RenderWorker};
+
+use crate::utils::{
+    IsacError, IsacInfo, LoadSaveFromJson,
+    metrics::METRICS,
+    render_cache::{RENDER_CACHE, RenderCache},
+};
 
-pub trait Render {
+pub trait Render: serde::Serialize {
     const RENDER_URL: &'static str; // Associated constant for the URL
-    async fn render(&self, client: &Client) -> Result<Bytes, IsacError>
-    where
-        Self: serde::Serialize,
-    {
-        Ok(client
-            .post(format!("http://localhost:3000/{}", Self::RENDER_URL))
-            .json(&self)
-            .send()
-            .await
-            .map_err(|_| IsacInfo::GeneralError {
-                msg: "screenshot failed".to_string(),
-            })?
-            .bytes()
+    async fn render(&self, client: &Client) -> Result<Bytes, IsacError> {
+        self.render_as(client, Self::RENDER_URL).await
+    }
+    /// render the same data with another template, e.g. `overall_tiers` of [`OverallTemplate`]
+    async fn render_as(&self, client: &Client, template: &str) -> Result<Bytes, IsacError> {
+        render_template(client, template, self).await
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, strum::IntoStaticStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum RenderBackend {
+    /// the python renderer, screenshots the `.hbs` in `renderer/template`
+    #[default]
+    Browser,
+    /// drawn in process by [`native`], only for the templates in [`native::SUPPORTED`]
+    Native,
+}
+
+/// how the images are rendered, edit the json to change it
+///
+/// written with the defaults on first run, e.g. drawing the leaderboard natively with two workers:
+/// ```json
+/// {"version":0,"data":{"backends":{"leaderboard":"native"},
+///  "workers":["http://localhost:3000/","http://10.0.0.2:3000/"],
+///  "timeout_secs":30,"cache_ttl_secs":3600,"cache_max_mb":512}}
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RendererConfig {
+    /// the backend of each template, e.g. `{"leaderboard": "native"}`, the browser if not listed
+    pub backends: HashMap<String, RenderBackend>,
+    /// the browser renderers, the ones on localhost are launched by the bot, see [`RENDER_POOL`]
+    pub workers: Vec<Url>,
+    /// a worker taking longer than this is skipped for the next one
+    pub timeout_secs: u64,
+    /// how long a rendered image is reused for the same data, see [`RENDER_CACHE`]
+    pub cache_ttl_secs: u64,
+    /// the size limit of the images cached on disk
+    pub cache_max_mb: u64,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        Self {
+            backends: HashMap::new(),
+            workers: vec![Url::parse("http://localhost:3000/").unwrap()],
+            timeout_secs: 30,
+            cache_ttl_secs: 3600,
+            cache_max_mb: 512,
+        }
+    }
+}
+
+impl LoadSaveFromJson for RendererConfig {
+    const PATH: &'static str = "./user_data/renderer.json";
+}
+
+impl RendererConfig {
+    /// the config loaded from [`RendererConfig::PATH`]
+    pub fn get() -> &'static Self {
+        static CONFIG: Lazy<RendererConfig> = Lazy::new(RendererConfig::load_json_sync);
+        &CONFIG
+    }
+
+    /// the backend for the template, falls back to the browser if [`native`] can't draw it
+    pub fn backend(&self, template: &str) -> RenderBackend {
+        match self.backends.get(template).copied().unwrap_or_default() {
+            RenderBackend::Native if !native::SUPPORTED.contains(&template) => {
+                warn!("no native renderer for {template}, using the browser");
+                RenderBackend::Browser
+            }
+            backend => backend,
+        }
+    }
+}
+
+/// where the browser renderer's `.hbs` live, its [`RenderCache::templates_version`] is in the cache
+/// key, so a changed template is rendered again instead of served from [`RENDER_CACHE`]
+///
+/// it's read once, a template deployed without restarting the bot still needs a cache flush
+const TEMPLATE_DIR: &str = "./renderer/template";
+static TEMPLATES_VERSION: Lazy<String> = Lazy::new(|| RenderCache::templates_version(TEMPLATE_DIR));
+
+/// render the data with the template's backend and get the image back
+async fn render_template(
+    client: &Client,
+    template: &str,
+    data: &(impl serde::Serialize + ?Sized),
+) -> Result<Bytes, IsacError> {
+    // the key is of the json, not the struct, so it's the same as what the renderer gets
+    let json = serde_json::to_value(data).map_err(|err| IsacInfo::GeneralError {
+        msg: format!("failed to serialize {template}: {err}"),
+    })?;
+    let backend = RendererConfig::get().backend(template);
+    // with the backend, so switching it in the config won't serve the other one's image
+    let key = RenderCache::key(
+        &format!(
+            "{template}:{}:{}",
+            <&str>::from(backend),
+            *TEMPLATES_VERSION
+        ),
+        &serde_json::to_vec(&json).unwrap_or_default(),
+    );
+    if let Some(img) = RENDER_CACHE.get(&key).await {
+        METRICS.inc("isac_render_cache_total", &[("result", "hit")]);
+        return Ok(img);
+    }
+    METRICS.inc("isac_render_cache_total", &[("result", "miss")]);
+    // the renderer may be restarting, give it a moment instead of failing right away
+    if backend == RenderBackend::Browser && !RENDER_POOL.wait_ready(Duration::from_secs(10)).await {
+        return Err(IsacInfo::GeneralError {
+            msg: "The renderer is restarting, please try again in a minute".to_string(),
+        }
+        .into());
+    }
+    let started = Instant::now();
+    let res = match backend {
+        RenderBackend::Browser => RENDER_POOL.post(client, template, &json).await,
+        RenderBackend::Native => native::render(template, json)
             .await
-            .map_err(|_| IsacInfo::GeneralError {
-                msg: "screenshot failed".to_string(),
-            })?)
+            .unwrap_or_else(|| Err(format!("no native renderer for {template}"))),
+    };
+    let labels = [("template", template), ("backend", <&str>::from(backend))];
+    METRICS.observe("isac_render_duration_seconds", &labels, started.elapsed());
+    if let Ok(img) = &res {
+        RENDER_CACHE.put(&key, img).await;
     }
+    res.map_err(|err| {
+        warn!("render {template} with {backend:?} failed: {err}");
+        METRICS.inc("isac_render_errors_total", &labels);
+        IsacInfo::GeneralError {
+            msg: "screenshot failed".to_string(),
+        }
+        .into()
+    })
 }
@@ -0,0 +1,833 @@
+//! the in-process renderer, draws the template json as SVG and rasterizes it with resvg
+//!
+//! it reads the same json as the `.hbs` in `renderer/template`, so a template can be migrated
+//! from the browser one at a time, see [`super::RendererConfig`]
+
+use std::{fmt::Write, sync::Arc};
+
+use bytes::Bytes;
+use once_cell::sync::Lazy;
+use resvg::{tiny_skia, usvg};
+use serde_json::Value;
+
+/// the templates [`draw`] knows
+pub const SUPPORTED: [&str; 7] = [
+    "leaderboard",
+    "kleaderboard",
+    "server_top",
+    "overall",
+    "recent",
+    "single_ship",
+    "clan",
+];
+
+const WIDTH: f64 = 1000.0;
+const PADDING: f64 = 16.0;
+/// the `font-size: 1.8em` of `.main`
+const FONT_SIZE: f64 = 28.8;
+const ROW_HEIGHT: f64 = 48.0;
+
+static FONTS: Lazy<Arc<usvg::fontdb::Database>> = Lazy::new(|| {
+    let mut fonts = usvg::fontdb::Database::new();
+    fonts.load_system_fonts();
+    // loaded last so it wins over a system one with the same name
+    let _ = fonts.load_font_file("./renderer/font/Roboto.ttf");
+    Arc::new(fonts)
+});
+
+/// render the template to png, None if the template is not in [`SUPPORTED`]
+pub async fn render(template: &str, data: Value) -> Option<Result<Bytes, String>> {
+    let svg = draw(template, &data)?;
+    Some(
+        tokio::task::spawn_blocking(move || rasterize(&svg))
+            .await
+            .map_err(|err| err.to_string())
+            .and_then(|res| res),
+    )
+}
+
+/// the template json to SVG, None if it's not supported
+pub fn draw(template: &str, data: &Value) -> Option<String> {
+    match template {
+        "leaderboard" => Some(leaderboard(data, "Leaderboard", "PR", "pr")),
+        "kleaderboard" => Some(leaderboard(data, "Avg Base Exp Leaderboard", "Bxp", "exp")),
+        "server_top" => Some(server_top(data)),
+        "overall" => Some(overall(data)),
+        "recent" => Some(recent(data)),
+        "single_ship" => Some(single_ship(data)),
+        "clan" => Some(clan(data)),
+        _ => None,
+    }
+}
+
+fn rasterize(svg: &str) -> Result<Bytes, String> {
+    let options = usvg::Options {
+        // the ship icons are local paths like `wowsinfo_data/live/app/assets/ships/xxx.png`
+        resources_dir: std::env::current_dir().ok(),
+        font_family: "Roboto".to_string(),
+        fontdb: Arc::clone(&FONTS),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|err| err.to_string())?;
+    let size = tree.size().to_int_size();
+    let mut pixmap =
+        tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("invalid image size")?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map(Bytes::from)
+        .map_err(|err| err.to_string())
+}
+
+/// the value at the dotted path, formatted like mustache in `renderer.py`
+fn field(data: &Value, path: &str) -> String {
+    let value = path
+        .split('.')
+        .try_fold(data, |value, key| value.get(key))
+        .unwrap_or(&Value::Null);
+    match value {
+        Value::String(s) => s.clone(),
+        // `no_fract_float_to_int`
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() && f.fract() == 0.0 => format!("{f:.0}"),
+            _ => n.to_string(),
+        },
+        Value::Bool(b) => b.to_string(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// the left edges of the grid columns in `grid-template-columns`, and the total width
+fn columns(fractions: &[f64], width: f64) -> Vec<(f64, f64)> {
+    let total: f64 = fractions.iter().sum();
+    let mut x = 0.0;
+    fractions
+        .iter()
+        .map(|fr| {
+            let w = width * fr / total;
+            x += w;
+            (x - w, w)
+        })
+        .collect()
+}
+
+/// a piece of text in a cell, (text, color, font scale)
+type Span = (String, String, f64);
+
+fn span(text: impl Into<String>, color: impl Into<String>) -> Vec<Span> {
+    vec![(text.into(), color.into(), 1.0)]
+}
+
+/// the `{value, color}` at the path, e.g. a [`crate::structs::StatisticValue`]
+fn stat(data: &Value, path: &str) -> Vec<Span> {
+    span(
+        field(data, &format!("{path}.value")),
+        field(data, &format!("{path}.color")),
+    )
+}
+
+/// [`stat`] with a `%`, e.g. the win rate
+fn percent(data: &Value, path: &str) -> Vec<Span> {
+    span(
+        format!("{}%", field(data, &format!("{path}.value"))),
+        field(data, &format!("{path}.color")),
+    )
+}
+
+/// the SVG drawn from top to bottom, each method is a block of the `.hbs`
+struct Canvas {
+    body: String,
+    y: f64,
+    tables: usize,
+}
+
+impl Canvas {
+    const INNER: f64 = WIDTH - PADDING * 2.0;
+    const CENTER: f64 = WIDTH / 2.0;
+
+    fn new() -> Self {
+        Self {
+            body: String::new(),
+            y: PADDING,
+            tables: 0,
+        }
+    }
+
+    /// a line of spans, centered or from the left padding
+    fn line(&mut self, spans: &[Span], size: f64, centered: bool) {
+        self.y += size * 1.3;
+        let (x, anchor) = match centered {
+            true => (Self::CENTER, "middle"),
+            false => (PADDING, "start"),
+        };
+        let _ = write!(
+            self.body,
+            r#"<text x="{x}" y="{}" font-size="{size}" font-weight="bold" text-anchor="{anchor}">"#,
+            self.y - size * 0.3
+        );
+        for (text, color, scale) in spans.iter().filter(|(text, ..)| !text.is_empty()) {
+            let _ = write!(
+                self.body,
+                r#"<tspan fill="{}" font-size="{}">{} </tspan>"#,
+                escape(color),
+                size * scale,
+                escape(text)
+            );
+        }
+        self.body.push_str("</text>");
+        self.y += 8.0;
+    }
+
+    /// `.user`, the clan tag, ign and region of the player
+    fn user(&mut self, data: &Value) {
+        let mut spans = vec![];
+        if !data["clan"].is_null() {
+            spans.extend(span(
+                format!("[{}]", field(data, "clan.tag")),
+                field(data, "clan.color"),
+            ));
+        }
+        spans.extend(span(field(data, "user.ign"), "white"));
+        spans.push((field(data, "user.region"), "#c8c8c8".to_string(), 0.6));
+        self.line(&spans, FONT_SIZE * 1.2, false);
+    }
+
+    /// `.global-title` and `.division-stats-title`
+    fn title(&mut self, spans: &[Span]) {
+        self.line(spans, FONT_SIZE * 1.2, true);
+    }
+
+    /// `.warship` of the leaderboards, the ship name and its icon
+    fn ship(&mut self, data: &Value) {
+        const ICON_W: f64 = 214.0;
+        const ICON_H: f64 = 126.0;
+        let ship = format!(
+            "{} {}",
+            field(data, "ship.tier_roman"),
+            field(data, "ship.name")
+        );
+        self.line(&span(ship, "white"), FONT_SIZE * 1.2 * 1.3, true);
+        let _ = write!(
+            self.body,
+            r#"<image href="{}" x="{}" y="{}" width="{ICON_W}" height="{ICON_H}"/>"#,
+            escape(&field(data, "ship.icon")),
+            Self::CENTER - ICON_W / 2.0,
+            self.y
+        );
+        self.y += ICON_H + 8.0;
+    }
+
+    /// `.divider`
+    fn divider(&mut self) {
+        let _ = write!(
+            self.body,
+            r##"<rect x="{PADDING}" y="{}" width="{}" height="2" fill="grey"/>"##,
+            self.y,
+            Self::INNER
+        );
+        self.y += 2.0 + 8.0;
+    }
+
+    /// `.global-pr`, the colored PR bar
+    fn pr(&mut self, data: &Value, path: &str) {
+        let height = FONT_SIZE * 2.0;
+        let _ = write!(
+            self.body,
+            r#"<rect x="{PADDING}" y="{}" width="{}" height="{height}" rx="10" fill="{}"/><text x="{}" y="{}" font-size="{FONT_SIZE}" font-weight="bold" text-anchor="middle">PR {}</text>"#,
+            self.y,
+            Self::INNER,
+            escape(&field(data, &format!("{path}.color"))),
+            Self::CENTER,
+            self.y + height / 2.0 + FONT_SIZE * 0.35,
+            escape(&field(data, &format!("{path}.value")))
+        );
+        self.y += height + 8.0;
+    }
+
+    /// `.global-stats-table`, the boxes of a label above its value, in one row
+    fn boxes(&mut self, boxes: &[(&str, Vec<Span>)]) {
+        let height = FONT_SIZE * 2.6;
+        let cols = columns(&vec![1.0; boxes.len()], Self::INNER);
+        for ((label, value), (x, w)) in boxes.iter().zip(cols) {
+            let center = PADDING + x + w / 2.0;
+            let _ = write!(
+                self.body,
+                r##"<rect x="{}" y="{}" width="{}" height="{height}" rx="10" fill="#404040"/><text x="{center}" y="{}" font-size="{}" fill="#c8c8c8" text-anchor="middle">{}</text>"##,
+                PADDING + x + 4.0,
+                self.y,
+                w - 8.0,
+                self.y + FONT_SIZE * 0.9,
+                FONT_SIZE * 0.6,
+                escape(label)
+            );
+            for (text, color, scale) in value {
+                let _ = write!(
+                    self.body,
+                    r#"<text x="{center}" y="{}" font-size="{}" font-weight="bold" fill="{}" text-anchor="middle">{}</text>"#,
+                    self.y + FONT_SIZE * 2.1,
+                    FONT_SIZE * 0.9 * scale,
+                    escape(color),
+                    escape(text)
+                );
+            }
+        }
+        self.y += height + 8.0;
+    }
+
+    /// `.per-ship-table`, striped rows clipped by its round corners
+    ///
+    /// the columns in `left` are aligned to the left, the others are centered
+    fn table(
+        &mut self,
+        labels: &[&str],
+        fractions: &[f64],
+        left: &[usize],
+        rows: Vec<Vec<Vec<Span>>>,
+    ) {
+        let cols = columns(fractions, Self::INNER);
+        let id = self.tables;
+        self.tables += 1;
+        let table_y = self.y;
+        let table_h = ROW_HEIGHT * (rows.len() + 1) as f64;
+        let inner = Self::INNER;
+        let _ = write!(
+            self.body,
+            r##"<clipPath id="table{id}"><rect x="{PADDING}" y="{table_y}" width="{inner}" height="{table_h}" rx="10"/></clipPath><rect x="{PADDING}" y="{table_y}" width="{inner}" height="{table_h}" rx="10" fill="#404040"/><g clip-path="url(#table{id})">"##
+        );
+        let font = FONT_SIZE * 0.9;
+        let cell = |body: &mut String, col: usize, row_y: f64, spans: &[Span], bold: bool| {
+            let (x, w) = cols[col];
+            let (x, anchor) = match left.contains(&col) {
+                true => (PADDING + x + 12.0, "start"),
+                false => (PADDING + x + w / 2.0, "middle"),
+            };
+            let weight = if bold { r#" font-weight="bold""# } else { "" };
+            let _ = write!(
+                body,
+                r#"<text x="{x}" y="{}" font-size="{font}" text-anchor="{anchor}"{weight}>"#,
+                row_y + ROW_HEIGHT / 2.0 + font * 0.35
+            );
+            let spans = spans.iter().filter(|(text, ..)| !text.is_empty());
+            for (i, (text, color, scale)) in spans.enumerate() {
+                let gap = if i == 0 { "" } else { " " };
+                let _ = write!(
+                    body,
+                    r#"<tspan fill="{}" font-size="{}">{gap}{}</tspan>"#,
+                    escape(color),
+                    font * scale,
+                    escape(text)
+                );
+            }
+            body.push_str("</text>");
+        };
+        for (col, label) in labels.iter().enumerate() {
+            cell(&mut self.body, col, self.y, &span(*label, "white"), false);
+        }
+        self.y += ROW_HEIGHT;
+        for (i, row) in rows.iter().enumerate() {
+            // the odd rows in the html, counting the header
+            if i % 2 == 0 {
+                let _ = write!(
+                    self.body,
+                    r##"<rect x="{PADDING}" y="{}" width="{inner}" height="{ROW_HEIGHT}" fill="#393939"/>"##,
+                    self.y
+                );
+            }
+            for (col, spans) in row.iter().enumerate() {
+                cell(&mut self.body, col, self.y, spans, true);
+            }
+            self.y += ROW_HEIGHT;
+        }
+        self.body.push_str("</g>");
+        self.y += 8.0;
+    }
+
+    /// a bar chart, like the chart.js one of `clan.hbs`
+    fn bars(&mut self, bars: &[(&str, f64)]) {
+        let height = 200.0;
+        let max = bars.iter().map(|(_, v)| *v).fold(1.0, f64::max);
+        let cols = columns(&vec![1.0; bars.len()], Self::INNER);
+        let bottom = self.y + height;
+        for ((label, value), (x, w)) in bars.iter().zip(cols) {
+            let bar_h = (height - FONT_SIZE) * value / max;
+            let center = PADDING + x + w / 2.0;
+            let _ = write!(
+                self.body,
+                r##"<rect x="{}" y="{}" width="{}" height="{bar_h}" fill="rgba(25, 107, 167, 0.5)"/><text x="{center}" y="{}" font-size="{}" text-anchor="middle">{value}</text><text x="{center}" y="{}" font-size="{}" fill="#c8c8c8" text-anchor="middle">{}</text>"##,
+                PADDING + x + w * 0.15,
+                bottom - FONT_SIZE - bar_h,
+                w * 0.7,
+                bottom - FONT_SIZE - bar_h - 4.0,
+                FONT_SIZE * 0.6,
+                bottom - FONT_SIZE * 0.2,
+                FONT_SIZE * 0.6,
+                escape(label)
+            );
+        }
+        self.y = bottom + 8.0;
+    }
+
+    fn finish(self) -> String {
+        let height = self.y + PADDING;
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{height}" font-family="Roboto, sans-serif" fill="white"><rect width="100%" height="100%" fill="#343434"/>{}</svg>"##,
+            self.body
+        )
+    }
+}
+
+/// the striped table of the leaderboards under the ship header
+fn leaderboard_table(
+    data: &Value,
+    title: &str,
+    labels: &[&str],
+    fractions: &[f64],
+    rows: Vec<Vec<Vec<Span>>>,
+) -> String {
+    let mut canvas = Canvas::new();
+    canvas.ship(data);
+    canvas.divider();
+    canvas.title(&span(title, "white"));
+    let labels: Vec<&str> = ["", "Player"].iter().chain(labels).copied().collect();
+    canvas.table(&labels, fractions, &[1], rows);
+    canvas.finish()
+}
+
+/// `leaderboard.hbs` and `kleaderboard.hbs`
+fn leaderboard(data: &Value, title: &str, rating_label: &str, rating_key: &str) -> String {
+    let rows = data["players"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|p| {
+            let color = field(p, "color");
+            vec![
+                span(field(p, "rank"), &color),
+                vec![
+                    (field(p, "clan"), color.clone(), 1.0),
+                    (field(p, "ign"), color.clone(), 1.0),
+                ],
+                span(field(p, "battles"), "white"),
+                stat(p, rating_key),
+                percent(p, "winrate"),
+                stat(p, "dmg"),
+                stat(p, "frags"),
+            ]
+        })
+        .collect();
+    let title = format!("{title} ({})", field(data, "region"));
+    leaderboard_table(
+        data,
+        &title,
+        &["Battles", rating_label, "Win rate", "Dmg", "Frags"],
+        &[0.5, 2.5, 1.0, 1.0, 1.2, 1.0, 1.0],
+        rows,
+    )
+}
+
+/// `server_top.hbs`
+fn server_top(data: &Value) -> String {
+    let rows = data["players"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|p| {
+            let color = field(p, "color");
+            vec![
+                span(field(p, "rank"), &color),
+                vec![
+                    (field(p, "clan"), color.clone(), 1.0),
+                    (field(p, "player.ign"), color.clone(), 1.0),
+                    (field(p, "player.region"), "#c8c8c8".to_string(), 0.5),
+                ],
+                span(field(p, "stats.battles"), "white"),
+                stat(p, "stats.pr"),
+                percent(p, "stats.winrate"),
+                stat(p, "stats.dmg"),
+                stat(p, "stats.frags"),
+                stat(p, "stats.planes"),
+            ]
+        })
+        .collect();
+    let title = format!("Leaderboard ( {} )", field(data, "server"));
+    leaderboard_table(
+        data,
+        &title,
+        &["Battles", "PR", "Win rate", "Dmg", "Frags", "Planes"],
+        &[0.5, 2.5, 1.0, 1.0, 1.2, 1.0, 1.0, 1.0],
+        rows,
+    )
+}
+
+/// `.global-stats-table` of a [`crate::structs::Statistic`]
+fn main_boxes(canvas: &mut Canvas, data: &Value, path: &str) {
+    let at = |key: &str| format!("{path}.{key}");
+    canvas.boxes(&[
+        ("Battles", span(field(data, &at("battles")), "white")),
+        ("Win rate", percent(data, &at("winrate"))),
+        ("Dmg", stat(data, &at("dmg"))),
+        ("Frags", stat(data, &at("frags"))),
+        ("Planes", stat(data, &at("planes"))),
+    ]);
+}
+
+/// a row of the battle types table, None if the stats are missing, e.g. no battles
+fn mode_row(data: &Value, name: &str, path: &str) -> Option<Vec<Vec<Span>>> {
+    let stats = path
+        .split('.')
+        .try_fold(data, |value, key| value.get(key))
+        .filter(|stats| !stats.is_null())?;
+    Some(vec![
+        span(name, "white"),
+        span(field(stats, "battles"), "white"),
+        stat(stats, "exp"),
+        stat(stats, "pr"),
+        percent(stats, "winrate"),
+        stat(stats, "dmg"),
+        stat(stats, "frags"),
+        stat(stats, "planes"),
+    ])
+}
+
+/// `.division-stats-section`, the divisions in `prefix` and the `others` modes
+fn modes_table(canvas: &mut Canvas, data: &Value, title: &str, prefix: &str) {
+    let at = |key: &str| match prefix {
+        "" => key.to_string(),
+        prefix => format!("{prefix}.{key}"),
+    };
+    let mut rows: Vec<_> = [
+        ("Solo", "pvp_solo"),
+        ("Div 2", "pvp_div2"),
+        ("Div 3", "pvp_div3"),
+    ]
+    .into_iter()
+    .filter_map(|(name, key)| mode_row(data, name, &at(key)))
+    .collect();
+    let others = at("others");
+    let others = others
+        .split('.')
+        .try_fold(data, |value, key| value.get(key))
+        .and_then(Value::as_array);
+    rows.extend(
+        others
+            .into_iter()
+            .flatten()
+            .filter_map(|mode| mode_row(mode, &field(mode, "name"), "stats")),
+    );
+    canvas.divider();
+    canvas.title(&span(title, "white"));
+    canvas.table(
+        &["", "BTL", "Bxp", "PR", "Win rate", "Dmg", "Frags", "Planes"],
+        &[1.3, 1.0, 1.0, 1.0, 1.2, 1.0, 1.0, 1.0],
+        &[0],
+        rows,
+    );
+}
+
+/// `overall.hbs`
+fn overall(data: &Value) -> String {
+    let mut canvas = Canvas::new();
+    canvas.user(data);
+    canvas.title(&span("Overall", "white"));
+    canvas.pr(data, "div.pvp.pr");
+    main_boxes(&mut canvas, data, "div.pvp");
+    modes_table(&mut canvas, data, "By battle type", "div");
+
+    let rows = [
+        ("DD", "dd"),
+        ("CA", "ca"),
+        ("BB", "bb"),
+        ("CV", "cv"),
+        ("SS", "ss"),
+    ]
+    .into_iter()
+    .map(|(name, key)| {
+        let at = |stat_key: &str| format!("class.{key}.{stat_key}");
+        vec![
+            span(name, "white"),
+            span(field(data, &at("battles")), "white"),
+            stat(data, &at("exp")),
+            percent(data, &at("winrate")),
+            stat(data, &at("dmg")),
+            stat(data, &at("frags")),
+        ]
+    })
+    .collect();
+    canvas.divider();
+    canvas.title(&span("By ship class", "white"));
+    canvas.table(
+        &["", "BTL", "Bxp", "Win rate", "Dmg", "Frags"],
+        &[1.0, 1.0, 1.0, 1.2, 1.0, 1.0],
+        &[0],
+        rows,
+    );
+    canvas.finish()
+}
+
+/// `recent.hbs`
+fn recent(data: &Value) -> String {
+    let mut canvas = Canvas::new();
+    canvas.user(data);
+    let title = if !data["session"].is_null() {
+        format!("Session ({}) stats", field(data, "session"))
+    } else if !data["day_to"].is_null() {
+        format!(
+            "{} ~ {} days ago stats",
+            field(data, "day"),
+            field(data, "day_to")
+        )
+    } else {
+        format!("Recent {} days stats", field(data, "day"))
+    };
+    canvas.title(&span(format!("{title} {}", field(data, "suffix")), "white"));
+    canvas.pr(data, "main.pr");
+    main_boxes(&mut canvas, data, "main");
+    modes_table(&mut canvas, data, "By battle type", "div");
+
+    let rows = data["ships"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|ship| {
+            vec![
+                span(field(ship, "info.tier_roman"), "white"),
+                span(field(ship, "info.short_name"), "white"),
+                span(field(ship, "stats.battles"), "white"),
+                stat(ship, "stats.exp"),
+                stat(ship, "stats.pr"),
+                percent(ship, "stats.winrate"),
+                stat(ship, "stats.dmg"),
+                stat(ship, "stats.frags"),
+                stat(ship, "stats.planes"),
+            ]
+        })
+        .collect();
+    canvas.divider();
+    canvas.title(&span("Stats by ship", "white"));
+    canvas.table(
+        &[
+            "Tier", "Warship", "BTL", "Bxp", "PR", "Win rate", "Dmg", "Frags", "Planes",
+        ],
+        &[0.6, 2.0, 0.8, 1.0, 1.0, 1.2, 1.0, 0.9, 0.9],
+        &[1],
+        rows,
+    );
+    canvas.finish()
+}
+
+/// `single_ship.hbs`
+fn single_ship(data: &Value) -> String {
+    let mut canvas = Canvas::new();
+    canvas.user(data);
+    let mut title = span(
+        format!(
+            "{} {} {}",
+            field(data, "ship.tier_roman"),
+            field(data, "ship.name"),
+            field(data, "suffix")
+        ),
+        "white",
+    );
+    if !data["ranking"].is_null() {
+        title.extend(span(format!("#{}", field(data, "ranking")), "#FFC71F"));
+    }
+    canvas.title(&title);
+    canvas.pr(data, "main_mode.pr");
+    main_boxes(&mut canvas, data, "main_mode");
+    let plain = |key: &str| span(field(data, &format!("main_mode.{key}")), "white");
+    canvas.boxes(&[
+        ("Potential", plain("potential")),
+        ("Scout dmg", plain("scout")),
+        (
+            "Hit rate",
+            span(format!("{}%", field(data, "main_mode.hitrate")), "white"),
+        ),
+        ("Survival", plain("survival")),
+        ("K/D", plain("kd")),
+        ("Avg. spotted", plain("spotted")),
+    ]);
+    canvas.boxes(&[
+        ("Lifetime max dmg", plain("max_dmg")),
+        ("Lifetime max frags", plain("max_frags")),
+        ("Lifetime max exp", plain("max_exp")),
+    ]);
+    if !data["sub_modes"].is_null() {
+        modes_table(&mut canvas, data, "By division type", "sub_modes");
+    }
+    canvas.finish()
+}
+
+/// `clan.hbs`
+fn clan(data: &Value) -> String {
+    let mut canvas = Canvas::new();
+    canvas.line(
+        &[
+            (
+                format!("[{}]", field(data, "info.tag")),
+                field(data, "info.color"),
+                1.0,
+            ),
+            (field(data, "info.region"), "#c8c8c8".to_string(), 0.6),
+        ],
+        FONT_SIZE * 1.2,
+        false,
+    );
+    canvas.title(&span(field(data, "info.name"), "white"));
+    canvas.boxes(&[
+        ("MBRs", span(field(data, "stats.members"), "white")),
+        (
+            "Actives",
+            span(field(data, "stats.active_members"), "white"),
+        ),
+        ("Win rate", percent(data, "stats.winrate")),
+        ("Dmg", stat(data, "stats.dmg")),
+        ("Exp", span(field(data, "stats.exp"), "white")),
+    ]);
+    let wr_dis = ["w0", "w45", "w50", "w55", "w60", "w65", "w70"]
+        .map(|key| data["stats"]["wr_dis"][key].as_f64().unwrap_or_default());
+    let labels = ["<45%", "45%", "50%", "55%", "60%", "65%", ">70%"];
+    canvas.bars(&labels.into_iter().zip(wr_dis).collect::<Vec<_>>());
+
+    let rating = |season: &Value, key: &str| {
+        vec![
+            (
+                field(season, &format!("{key}.division")),
+                field(season, &format!("{key}.color")),
+                1.0,
+            ),
+            (
+                field(season, &format!("{key}.division_rating")),
+                "white".to_string(),
+                1.0,
+            ),
+        ]
+    };
+    let rows = data["seasons"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|season| {
+            vec![
+                span(format!("S{}", field(season, "season")), "white"),
+                span(field(season, "battles"), "white"),
+                percent(season, "winrate"),
+                rating(season, "now"),
+                rating(season, "max"),
+            ]
+        })
+        .collect();
+    canvas.divider();
+    canvas.title(&span("Clan Statistic", "white"));
+    canvas.table(
+        &["", "BTL", "Win rate", "Rating", "Record"],
+        &[1.0, 1.0, 1.0, 1.2, 1.2],
+        &[],
+        rows,
+    );
+    if !data["rename"].is_null() {
+        canvas.divider();
+        let former = format!(
+            "Former: [{}] {} ({})",
+            field(data, "rename.tag"),
+            field(data, "rename.name"),
+            field(data, "rename.time")
+        );
+        canvas.line(&span(former, "#c8c8c8"), FONT_SIZE * 0.8, true);
+    }
+    canvas.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{draw, rasterize};
+
+    #[test]
+    fn leaderboard_to_png() {
+        let player = json!({
+            "color": "#fff", "rank": 1, "clan": "[FIX]", "ign": "<fixture>", "uid": 1, "battles": 120,
+            "pr": {"value": 1500.0, "color": "#9D42F3"},
+            "winrate": {"value": 61.25, "color": "#D042F3"},
+            "frags": {"value": 1.2, "color": "#02C9B3"},
+            "dmg": {"value": 90000.0, "color": "#318000"},
+            "exp": {"value": 0.0, "color": "#FFFFFF"},
+        });
+        let data = json!({
+            "ship": {"tier_roman": "X", "name": "Fixture", "icon": "missing.png"},
+            "region": "ASIA",
+            "players": [player.clone(), player],
+        });
+        let svg = draw("leaderboard", &data).unwrap();
+        // the floats without fraction are printed as integers, like `renderer.py`
+        assert!(svg.contains(">1500<") && svg.contains(">61.25%<"));
+        assert!(svg.contains("&lt;fixture&gt;"));
+        let png = rasterize(&svg).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        assert!(draw("overall_tiers", &data).is_none());
+    }
+
+    #[test]
+    fn player_templates_to_png() {
+        let value = |value: f64| json!({"value": value, "color": "#44B300"});
+        let stats = json!({
+            "battles": 10, "winrate": value(55.5), "dmg": value(50000.0), "frags": value(1.1),
+            "planes": value(0.5), "pr": value(1234.0), "exp": value(1100.0), "potential": 80000,
+            "scout": 9000, "hitrate": 31.5, "survival": "-", "kd": 1.5, "spotted": "-",
+            "max_dmg": 200000, "max_frags": 6, "max_exp": 3000,
+        });
+        let user = json!({"ign": "fixture", "region": "ASIA", "karma": 1});
+        let clan =
+            json!({"tag": "FIX", "color": "#fff", "id": 1, "name": "Fixture", "region": "ASIA"});
+        let div = json!({
+            "pvp": stats, "pvp_solo": stats, "pvp_div2": null, "pvp_div3": stats,
+            "others": [{"mode": "pve", "name": "Co-op", "stats": stats}],
+        });
+        let class = json!({"ss": stats, "dd": stats, "ca": stats, "bb": stats, "cv": stats});
+        let ship = json!({"tier_roman": "X", "name": "Fixture", "short_name": "Fix"});
+        let overall = json!({"div": div, "class": class, "clan": clan, "user": user});
+        let recent = json!({
+            "clan": null, "user": user, "day": 7, "day_to": null, "session": null,
+            "suffix": "PVP", "main": stats, "div": div,
+            "ships": [{"info": ship, "stats": stats}],
+        });
+        let single_ship = json!({
+            "ship": ship, "ranking": 3, "suffix": "PVP", "main_mode": stats,
+            "sub_modes": {"pvp_solo": stats, "pvp_div2": stats, "pvp_div3": stats},
+            "clan": clan, "user": user,
+        });
+        let rating =
+            json!({"color": "#cda4ff", "league": 0, "division": "I", "division_rating": 50});
+        let clan = json!({
+            "info": clan,
+            "seasons": [{"season": 25, "battles": 30, "winrate": value(60.0), "win_streak": 3, "now": rating, "max": rating}],
+            "rename": {"tag": "OLD", "name": "Old", "time": "2024-01-01"},
+            "stats": {
+                "members": 40, "active_members": 20, "winrate": value(52.0), "dmg": value(60000.0), "exp": 1200,
+                "wr_dis": {"w0": 1, "w45": 2, "w50": 8, "w55": 5, "w60": 3, "w65": 1, "w70": 0},
+            },
+        });
+        for (template, data) in [
+            ("overall", overall),
+            ("recent", recent),
+            ("single_ship", single_ship),
+            ("clan", clan),
+        ] {
+            let svg = draw(template, &data).unwrap();
+            assert!(
+                svg.contains(">55.5%<") || svg.contains(">60%<"),
+                "{template}"
+            );
+            let png = rasterize(&svg).unwrap();
+            assert!(png.starts_with(b"\x89PNG"), "{template}");
+        }
+    }
+}
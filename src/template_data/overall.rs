@@ -1,12 +1,11 @@
-use bytes::Bytes;
-use reqwest::Client;
+use parking_lot::RwLock;
+use poise::ChoiceParameter;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use super::Render;
-use crate::{
-    structs::{PartialClan, Player, ShipClass, ShipTier, Statistic},
-    utils::{IsacError, IsacInfo},
+use crate::structs::{
+    ExpectedJs, Mode, PartialClan, Player, ShipClass, ShipStatsCollection, ShipTier, Statistic,
 };
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -34,22 +33,8 @@ impl OverallTemplate {
     //     };
     //     reg.render("overall", self).unwrap()
     // }
-    // QA trait化了, 但是這個struct同時要兩個版本 該怎麼寫?
-    pub async fn render_tiers(&self, client: &Client) -> Result<Bytes, IsacError> {
-        Ok(client
-            .post("http://localhost:3000/overall_tiers")
-            .json(&self)
-            .send()
-            .await
-            .map_err(|_| IsacInfo::GeneralError {
-                msg: "screenshot failed".to_string(),
-            })?
-            .bytes()
-            .await
-            .map_err(|_| IsacInfo::GeneralError {
-                msg: "screenshot failed".to_string(),
-            })?)
-    }
+    /// the template of [`OverallTemplate::tier`] only, for [`Render::render_as`]
+    pub const TIERS_RENDER_URL: &'static str = "overall_tiers";
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -58,6 +43,7 @@ pub struct OverallTemplateDiv {
     pub pvp_solo: Statistic,
     pub pvp_div2: Statistic,
     pub pvp_div3: Statistic,
+    pub others: Vec<OverallTemplateMode>,
 }
 impl OverallTemplateDiv {
     pub fn new(
@@ -65,16 +51,44 @@ impl OverallTemplateDiv {
         pvp_solo: Statistic,
         pvp_div2: Statistic,
         pvp_div3: Statistic,
+        others: Vec<OverallTemplateMode>,
     ) -> Self {
         Self {
             pvp,
             pvp_solo,
             pvp_div2,
             pvp_div3,
+            others,
         }
     }
 }
 
+/// a row of the modes outside the random battles, see [`Mode::others`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OverallTemplateMode {
+    pub mode: Mode,
+    pub name: String,
+    pub stats: Statistic,
+}
+
+impl OverallTemplateMode {
+    /// the rows of [`Mode::others`], modes without battles are skipped
+    pub fn collect(
+        ships: &ShipStatsCollection,
+        expected_js: &Arc<RwLock<ExpectedJs>>,
+    ) -> Vec<Self> {
+        Mode::others()
+            .filter_map(|mode| {
+                ships.to_statistic(expected_js, mode).map(|stats| Self {
+                    mode,
+                    name: mode.name().to_string(),
+                    stats,
+                })
+            })
+            .collect()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OverallTemplateTier {
     #[serde(rename = "1")]
@@ -0,0 +1,249 @@
+//! the renderer workers of [`super::RendererConfig::workers`], requests are spread over the ready ones
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::{Client, Url};
+use tokio::sync::watch;
+use tracing::warn;
+
+use super::RendererConfig;
+
+/// a worker unreachable, timed out or answering 5xx is tried last for a while
+const DEMOTE_FOR: Duration = Duration::from_secs(30);
+
+pub struct RenderWorker {
+    pub url: Url,
+    /// the time until it's tried last, after it was unreachable, timed out or answered 5xx
+    demoted_until: Mutex<Option<Instant>>,
+}
+
+impl RenderWorker {
+    /// a worker on this machine, launched and restarted by [`crate::tasks::renderer_supervisor`]
+    ///
+    /// the port must be explicit, `http://localhost/` is not launched on port 80
+    pub fn local_port(&self) -> Option<u16> {
+        match self.url.host_str() {
+            Some("localhost" | "127.0.0.1") => self.url.port(),
+            _ => None,
+        }
+    }
+
+    pub fn health_url(&self) -> Url {
+        self.url.join("health").unwrap()
+    }
+
+    fn demoted(&self) -> bool {
+        self.demoted_until
+            .lock()
+            .is_some_and(|until| until > Instant::now())
+    }
+}
+
+pub struct RenderPool {
+    pub workers: Vec<RenderWorker>,
+    /// whether each of the workers passed its last health check
+    ready: watch::Sender<Vec<bool>>,
+    next: AtomicUsize,
+}
+
+pub static RENDER_POOL: Lazy<RenderPool> =
+    Lazy::new(|| RenderPool::new(RendererConfig::get().workers.clone()));
+
+impl RenderPool {
+    fn new(mut urls: Vec<Url>) -> Self {
+        if urls.is_empty() {
+            urls = RendererConfig::default().workers;
+        }
+        let workers: Vec<_> = urls
+            .into_iter()
+            .map(|mut url| {
+                // so `join` keeps the path of the url
+                if !url.path().ends_with('/') {
+                    url.set_path(&format!("{}/", url.path()));
+                }
+                RenderWorker {
+                    url,
+                    demoted_until: Mutex::new(None),
+                }
+            })
+            .collect();
+        for worker in workers.iter() {
+            if worker.local_port().is_none()
+                && matches!(worker.url.host_str(), Some("localhost" | "127.0.0.1"))
+            {
+                warn!(
+                    "renderer {} has no port, it won't be launched, only health checked",
+                    worker.url
+                );
+            }
+        }
+        Self {
+            ready: watch::channel(vec![false; workers.len()]).0,
+            workers,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn worker_ready(&self, index: usize) -> bool {
+        self.ready.borrow()[index]
+    }
+
+    pub fn set_ready(&self, index: usize, ready: bool) {
+        self.ready.send_modify(|workers| workers[index] = ready);
+    }
+
+    /// wait until any worker is ready, false if none is after the timeout
+    pub async fn wait_ready(&self, timeout: Duration) -> bool {
+        let mut ready = self.ready.subscribe();
+        tokio::time::timeout(
+            timeout,
+            ready.wait_for(|workers| workers.iter().any(|ready| *ready)),
+        )
+        .await
+        .is_ok_and(|res| res.is_ok())
+    }
+
+    /// the workers to try in order, round robin over the ready ones, then the rest as the last resort
+    fn order(&self) -> Vec<&RenderWorker> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let ready = self.ready.borrow().clone();
+        let n = self.workers.len();
+        let (mut first, rest): (Vec<_>, Vec<_>) = (0..n)
+            .map(|i| (start + i) % n)
+            .partition(|&i| ready[i] && !self.workers[i].demoted());
+        first.extend(rest);
+        first.into_iter().map(|i| &self.workers[i]).collect()
+    }
+
+    /// post the data to the workers until one of them gives the image back
+    ///
+    /// only an unreachable, timed out or 5xx worker, e.g. a crashed browser, is skipped for the
+    /// next one, other errors, e.g. a bad template or data, would fail on every worker, so they are
+    /// returned right away
+    pub async fn post(
+        &self,
+        client: &Client,
+        template: &str,
+        data: &(impl serde::Serialize + ?Sized),
+    ) -> Result<Bytes, String> {
+        let timeout = Duration::from_secs(RendererConfig::get().timeout_secs);
+        let mut last_err = String::from("no renderer worker");
+        for worker in self.order() {
+            let url = worker.url.join(template).map_err(|err| err.to_string())?;
+            let res = async {
+                client
+                    .post(url)
+                    .json(data)
+                    .timeout(timeout)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .bytes()
+                    .await
+            }
+            .await;
+            match res {
+                Ok(img) => return Ok(img),
+                Err(err)
+                    if err.is_connect()
+                        || err.is_timeout()
+                        || err.status().is_some_and(|status| status.is_server_error()) =>
+                {
+                    let err = err.without_url().to_string();
+                    warn!("renderer {} failed {template}: {err}", worker.url);
+                    *worker.demoted_until.lock() = Some(Instant::now() + DEMOTE_FOR);
+                    last_err = err;
+                }
+                Err(err) => return Err(err.without_url().to_string()),
+            }
+        }
+        Err(last_err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::Url;
+
+    use super::RenderPool;
+
+    #[test]
+    fn round_robin_over_ready_workers() {
+        let urls = [
+            "http://localhost:3000",
+            "http://localhost:3001/",
+            "http://10.0.0.2:3000/r",
+        ];
+        let pool = RenderPool::new(urls.iter().map(|url| Url::parse(url).unwrap()).collect());
+        assert_eq!(pool.workers[2].url.as_str(), "http://10.0.0.2:3000/r/");
+        assert_eq!(pool.workers[1].local_port(), Some(3001));
+        assert_eq!(pool.workers[2].local_port(), None);
+        let no_port = RenderPool::new(vec![Url::parse("http://localhost/").unwrap()]);
+        assert_eq!(no_port.workers[0].local_port(), None);
+
+        pool.set_ready(0, true);
+        pool.set_ready(2, true);
+        let first = |pool: &RenderPool| pool.order()[0].url.host_str().map(String::from);
+        let local = Some("localhost".to_string());
+        let remote = Some("10.0.0.2".to_string());
+        // the worker not ready is never tried first
+        let firsts: Vec<_> = (0..3).map(|_| first(&pool)).collect();
+        assert_eq!(firsts, [local.clone(), remote.clone(), remote.clone()]);
+        assert_eq!(pool.order().len(), 3);
+
+        // nor the one just failed
+        *pool.workers[2].demoted_until.lock() =
+            Some(std::time::Instant::now() + std::time::Duration::from_secs(60));
+        assert!((0..3).all(|_| first(&pool) == local));
+    }
+
+    #[tokio::test]
+    async fn fail_over_only_when_unreachable_or_5xx() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // answers every request with the status line
+        async fn serve(status: &'static str) -> u16 {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let _ = stream.read(&mut [0; 4096]).await;
+                    let res = format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\n\r\n");
+                    let _ = stream.write_all(res.as_bytes()).await;
+                }
+            });
+            port
+        }
+        // like a crashed browser, and a template error
+        let broken_port = serve("500 Internal Server Error").await;
+        let bad_port = serve("400 Bad Request").await;
+        // nothing listens there
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed_port = closed.local_addr().unwrap().port();
+        drop(closed);
+
+        let urls = [
+            format!("http://127.0.0.1:{closed_port}/"),
+            format!("http://127.0.0.1:{broken_port}/"),
+            format!("http://127.0.0.1:{bad_port}/"),
+            format!("http://127.0.0.1:{closed_port}/"),
+        ];
+        let pool = RenderPool::new(urls.iter().map(|url| Url::parse(url).unwrap()).collect());
+        let err = pool
+            .post(&reqwest::Client::new(), "overall", &())
+            .await
+            .unwrap_err();
+        assert!(err.contains("400"), "{err}");
+        assert!(pool.workers[0].demoted());
+        assert!(pool.workers[1].demoted());
+        // the bad request is not retried on the next worker, nor demoting this one
+        assert!(!pool.workers[2].demoted());
+        assert!(!pool.workers[3].demoted());
+    }
+}
@@ -1,15 +1,24 @@
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use super::Render;
-use crate::structs::{Mode, PartialClan, Player, Ship, Statistic};
+use super::{OverallTemplateClass, OverallTemplateMode, OverallTemplateTier, Render};
+use crate::{
+    Context,
+    structs::{Mode, PartialClan, Player, Ship, ShipStatsCollection, Statistic},
+};
+
+/// only show the top X ships in the ship list
+const RECENT_OMIT_LIMIT: usize = 50;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RecentTemplate {
     pub clan: Option<PartialClan>,
     pub user: Player,
     pub ships: Vec<RecentTemplateShip>,
-    pub day: u64,       // the exact_day
-    pub suffix: String, // mode.render_name()
+    pub day: u64,                // the exact_day
+    pub day_to: Option<u64>,     // the end of the range, None for now
+    pub session: Option<String>, // the session duration, None if it's not a session
+    pub suffix: String,          // mode.render_name()
     pub main: Statistic,
     pub div: RecentTemplateDiv,
 }
@@ -18,13 +27,58 @@ impl Render for RecentTemplate {
     const RENDER_URL: &'static str = "recent";
 }
 
+impl RecentTemplate {
+    /// build the template with the stats diff, `day`, `day_to` and `session` are left empty
+    pub fn new(
+        ctx: &Context<'_>,
+        stats: &ShipStatsCollection,
+        mode: Mode,
+        clan: Option<PartialClan>,
+        player: Player,
+    ) -> Self {
+        let expected = &ctx.data().expected;
+        let div = RecentTemplateDiv {
+            pvp: stats.to_statistic(expected, Mode::Pvp),
+            pvp_solo: stats.to_statistic(expected, Mode::Solo),
+            pvp_div2: stats.to_statistic(expected, Mode::Div2),
+            pvp_div3: stats.to_statistic(expected, Mode::Div3),
+            others: OverallTemplateMode::collect(stats, expected),
+        };
+        let ships = stats
+            .0
+            .iter()
+            .filter_map(|(ship_id, ship_stats)| {
+                ship_stats
+                    .to_statistic(ship_id, expected, mode)
+                    .map(|stats| RecentTemplateShip {
+                        info: ship_id.get_ship(&ctx.data().ships).unwrap_or_default(),
+                        stats,
+                    })
+            })
+            .sorted_by_key(|ship| (-(ship.stats.battles as i64), -(ship.info.tier as i64)))
+            .take(RECENT_OMIT_LIMIT)
+            .collect_vec();
+        Self {
+            clan,
+            user: player,
+            ships,
+            day: 0,
+            day_to: None,
+            session: None,
+            suffix: mode.render_name().to_string(),
+            main: div.get_mode(&mode).cloned().unwrap_or_default(),
+            div,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RecentTemplateDiv {
     pub pvp: Option<Statistic>,
     pub pvp_solo: Option<Statistic>,
     pub pvp_div2: Option<Statistic>,
     pub pvp_div3: Option<Statistic>,
-    pub rank_solo: Option<Statistic>,
+    pub others: Vec<OverallTemplateMode>,
 }
 
 impl RecentTemplateDiv {
@@ -34,7 +88,11 @@ impl RecentTemplateDiv {
             Mode::Solo => self.pvp_solo.as_ref(),
             Mode::Div2 => self.pvp_div2.as_ref(),
             Mode::Div3 => self.pvp_div3.as_ref(),
-            Mode::Rank => self.rank_solo.as_ref(),
+            _ => self
+                .others
+                .iter()
+                .find(|row| &row.mode == mode)
+                .map(|row| &row.stats),
         }
     }
 }
@@ -44,3 +102,39 @@ pub struct RecentTemplateShip {
     pub info: Ship,
     pub stats: Statistic,
 }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecentTimelineTemplate {
+    pub clan: Option<PartialClan>,
+    pub user: Player,
+    pub day: u64,
+    pub day_to: Option<u64>,
+    pub suffix: String,
+    pub days: Vec<RecentTimelineDay>,
+}
+
+impl Render for RecentTimelineTemplate {
+    const RENDER_URL: &'static str = "recent_timeline";
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecentTimelineDay {
+    pub date: String,             // MM/DD of the later snapshot
+    pub stats: Option<Statistic>, // None if no battles that day
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecentBreakdownTemplate {
+    pub clan: Option<PartialClan>,
+    pub user: Player,
+    pub day: u64,
+    pub day_to: Option<u64>,
+    pub suffix: String,
+    pub main: Statistic,
+    pub class: OverallTemplateClass,
+    pub tier: OverallTemplateTier,
+}
+
+impl Render for RecentBreakdownTemplate {
+    const RENDER_URL: &'static str = "recent_breakdown";
+}
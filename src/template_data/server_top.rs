@@ -19,6 +19,7 @@ pub struct ServerTopPlayer {
     pub color: String, // hex
     pub rank: usize,
     pub clan: String, // empty if no clan
+    /// from [`crate::utils::wws_api::WowsApi::full_players`], the dogtag is empty and the karma is 0 if private
     pub player: Player,
     pub stats: Statistic,
 }
@@ -1,7 +1,3 @@
-use bytes::Bytes;
-
-use reqwest::Client;
-
 use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct};
 
 use super::Render;
@@ -42,21 +38,6 @@ where
     }
 }
 impl SingleShipTemplate {
-    pub async fn render(&self, client: &Client) -> Result<Bytes, IsacError> {
-        Ok(client
-            .post("http://localhost:3000/single_ship")
-            .json(&self)
-            .send()
-            .await
-            .map_err(|_| IsacInfo::GeneralError {
-                msg: "screenshot failed".to_string(),
-            })?
-            .bytes()
-            .await
-            .map_err(|_| IsacInfo::GeneralError {
-                msg: "screenshot failed".to_string(),
-            })?)
-    }
     // QA 這種方式真的算正面嗎?
     /// a helper function to build up the structure, raise [`IsacInfo::PlayerNoBattleShip`] if the main_mode battle_counts is 0
     #[allow(clippy::too_many_arguments)]
@@ -78,7 +59,7 @@ impl SingleShipTemplate {
                 mode,
             })?
         };
-        let sub_modes = if let Mode::Rank = mode {
+        let sub_modes = if !mode.is_pvp() {
             None
         } else {
             Some(SingleShipTemplateSub::new(
@@ -1,6 +1,15 @@
 pub mod cache_methods;
 pub mod error_handler;
+pub mod metrics;
+#[cfg(test)]
+pub mod mock_upstream;
 pub mod parse;
+pub mod render_cache;
+pub mod request_policy;
+pub mod response_cache;
+pub mod snapshot_store;
+pub mod upstream_health;
+pub mod wg_query;
 pub mod wws_api;
 
 mod isac_error;
@@ -11,7 +11,7 @@ use poise::{
 
 use crate::{
     Context, Data, Error,
-    utils::{IsacError, IsacHelp},
+    utils::{IsacError, IsacHelp, metrics},
 };
 
 // TODO: might need to be moved to a file for consts
@@ -43,6 +43,7 @@ pub async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
             ctx,
             ..
         } => {
+            metrics::command_finished(ctx, Some(&error)).await;
             if let Some(isac_err) = error.downcast_ref::<IsacError>() {
                 isac_err_handler(&ctx, isac_err).await;
             } else {
@@ -63,6 +64,7 @@ pub async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
         }
 
         poise::FrameworkError::Command { error, ctx, .. } => {
+            metrics::command_finished(ctx, Some(&error)).await;
             // errors returned here, include discord shits
             if let Some(isac_err) = error.downcast_ref::<IsacError>() {
                 isac_err_handler(&ctx, isac_err).await;
@@ -78,6 +80,7 @@ pub async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
             }
         }
         poise::FrameworkError::CommandPanic { payload, ctx, .. } => {
+            metrics::command_finished(ctx, Some(&"panicked".into())).await;
             isac_get_help(&ctx, None).await;
             isac_err_logging(
                 &ctx,
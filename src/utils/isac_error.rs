@@ -3,6 +3,7 @@ use std::fmt::{self, Display};
 use crate::{
     Error,
     structs::{Mode, PartialClan, Region},
+    utils::upstream_health::UPSTREAM_HEALTH,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -17,12 +18,26 @@ pub enum IsacError {
     UnknownError(#[from] Error),
 }
 
+impl IsacError {
+    /// the error kind for the metrics, e.g. `PlayerHidden`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IsacError::Help(_) => "Help",
+            IsacError::Info(info) => info.into(),
+            IsacError::Cancelled => "Cancelled",
+            IsacError::UnknownError(_) => "UnknownError",
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum IsacHelp {
     #[error("Click the button to check commands' usage and examples")]
     LackOfArguments,
+    #[error("**{to_day}** days ago should be later than **{from_day}** days ago")]
+    InvalidDayRange { from_day: u64, to_day: u64 },
 }
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, thiserror::Error, strum::IntoStaticStr)]
 pub enum IsacInfo {
     /// give None if its author himself
     UserNotLinked {
@@ -95,7 +110,10 @@ impl Display for IsacInfo {
             IsacInfo::TooShortIgn { ign } => {
                 format!("❌ At least 3 charactars for ign searching: `{ign}`")
             }
-            IsacInfo::APIError { msg } => format!("❌ API error: `{msg}`"),
+            IsacInfo::APIError { msg } => format!(
+                "❌ API error: `{msg}`{}",
+                UPSTREAM_HEALTH.down_note().unwrap_or_default()
+            ),
             IsacInfo::InvalidIgn { ign } => format!("❌ Invalid ign: `{ign}`"),
             IsacInfo::PlayerIgnNotFound { ign, region } => {
                 format!("Player: `{ign}` not found in `{region}`")
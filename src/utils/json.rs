@@ -1,131 +1,287 @@
-use std::{fs, io::Write, path::Path};
+use std::{
+    fmt::Display,
+    fs,
+    io::Write,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use serde::{Serialize, de::DeserializeOwned};
-use tracing::{error, warn};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use tracing::{error, info, warn};
+
+/// upgrade the json `data` of a version to the next one
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// the file format of [`LoadSaveFromJson`], files without it are treated as version 0
+#[derive(Serialize)]
+struct VersionedJson<'a, T> {
+    version: usize,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VersionedJsonAny {
+    version: usize,
+    data: Value,
+}
+
+/// run the `migrations` from `version` to the latest, `migrations[i]` upgrades version `i` to `i + 1`
+///
+/// Err if the `version` is newer than the `migrations` know, or a migration failed
+pub fn migrate(version: usize, mut data: Value, migrations: &[Migration]) -> Result<Value, String> {
+    let current = migrations.len();
+    if version > current {
+        return Err(format!(
+            "version {version} is newer than the supported {current}"
+        ));
+    }
+    for (from, migration) in migrations.iter().enumerate().skip(version) {
+        data = migration(data)
+            .map_err(|err| format!("migration from version {from} failed, Err: {err}"))?;
+    }
+    Ok(data)
+}
 
 /// load and save the struct with given json path
 ///
-/// ## Panic
-/// panic when failing to load from the path
+/// The file is wrapped as `{"version": X, "data": ...}`, to change the format, push a
+/// [`Migration`] into [`LoadSaveFromJson::MIGRATIONS`] with a test, old files will be upgraded
+/// on load.
+///
+/// A file newer than [`LoadSaveFromJson::MIGRATIONS`], e.g. written by a newer build before a
+/// rollback, is quarantined like a broken one instead of being overwritten.
+///
+/// A failed save doesn't panic, it's logged and reported over the webhook logger, see
+/// [`LoadSaveFromJson::save_json`].
 pub trait LoadSaveFromJson: Serialize + DeserializeOwned {
     const PATH: &'static str;
+    /// `MIGRATIONS[i]` upgrades version `i` to `i + 1`, the current version is its length
+    const MIGRATIONS: &'static [Migration] = &[];
 
-    async fn load_json() -> Self
-    where
-        Self: Default + Sized + Send + 'static,
-    {
-        tokio::task::spawn_blocking(move || {
-            if let Ok(file) = std::fs::File::open(Self::PATH) {
-                let json_str = std::io::read_to_string(file).unwrap();
-                serde_json::from_str(&json_str).unwrap_or_else(|err| {
-                    panic!(
-                        "Failed to deserialize file: {:#?} to struct: {}\n Err: {err}",
-                        Self::PATH,
-                        std::any::type_name::<Self>()
-                    )
-                })
-            } else {
-                warn!(
-                    "file: {:#?} wasn't existed, initializing a dafault one",
-                    Self::PATH
-                );
-                let default = Self::default();
-                default.save_json_sync();
-                default
-            }
+    /// parse the file content, return the struct and whether it was migrated
+    fn from_versioned_str(json_str: &str) -> Result<(Self, bool), String> {
+        let value: Value = serde_json::from_str(json_str).map_err(|err| err.to_string())?;
+        let (version, data) = match serde_json::from_value::<VersionedJsonAny>(value.clone()) {
+            Ok(versioned) => (versioned.version, versioned.data),
+            // not wrapped yet
+            Err(_) => (0, value),
+        };
+        let data = migrate(version, data, Self::MIGRATIONS)?;
+        let loaded = serde_json::from_value(data).map_err(|err| err.to_string())?;
+        Ok((loaded, version != Self::MIGRATIONS.len()))
+    }
+
+    fn to_versioned_vec(&self) -> Vec<u8> {
+        serde_json::to_vec(&VersionedJson {
+            version: Self::MIGRATIONS.len(),
+            data: self,
         })
-        .await
         .unwrap_or_else(|err| {
             panic!(
-                "Failed to join async load_json for file: {:?}. Err: {err}",
-                Self::PATH
+                "Failed to serialize struct: {:?} to JSON. Err: {err}",
+                std::any::type_name::<Self>(),
             )
         })
     }
 
+    async fn load_json() -> Self
+    where
+        Self: Default + Sized + Send + 'static,
+    {
+        tokio::task::spawn_blocking(Self::load_json_sync)
+            .await
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Failed to join async load_json for file: {:?}. Err: {err}",
+                    Self::PATH
+                )
+            })
+    }
+
+    /// a broken file will be quarantined, see [`quarantine_file`], then fallback to the default
     fn load_json_sync() -> Self
     where
         Self: Default,
     {
         if let Ok(file) = std::fs::File::open(Self::PATH) {
-            let json_str = std::io::read_to_string(file).unwrap();
-            serde_json::from_str(&json_str).unwrap_or_else(|err| {
-                panic!(
-                    "Failed to deserialize file: {:?} to struct: {}\n Err: {err}",
+            match std::io::read_to_string(file)
+                .map_err(|err| err.to_string())
+                .and_then(|json_str| Self::from_versioned_str(&json_str))
+            {
+                Ok((loaded, migrated)) => {
+                    if migrated {
+                        info!(
+                            "file: {} migrated to version {}",
+                            Self::PATH,
+                            Self::MIGRATIONS.len()
+                        );
+                        loaded.save_json_sync();
+                    }
+                    return loaded;
+                }
+                Err(err) => quarantine_file(
                     Self::PATH,
-                    std::any::type_name::<Self>()
-                )
-            })
+                    format!("to struct: {}, Err: {err}", std::any::type_name::<Self>()),
+                ),
+            }
         } else {
             warn!(
                 "file: {} wasn't existed, initializing a dafault one",
                 Self::PATH
             );
-            let default = Self::default();
-            default.save_json_sync();
-            default
         }
+        let default = Self::default();
+        default.save_json_sync();
+        default
     }
 
+    /// a failed write is reported by [`report_save_error`], the old file is kept
     async fn save_json(&self)
     where
         Self: Sized,
     {
-        let json_bytes = serde_json::to_vec(&self).unwrap_or_else(|err| {
-            panic!(
-                "Failed to serialize struct: {:?} to JSON. Err: {err}",
-                std::any::type_name::<Self>(),
-            )
-        });
-        tokio::task::spawn_blocking(move || {
-            save_file_with_lock(Self::PATH, &json_bytes);
-        })
-        .await
-        .unwrap_or_else(|err| {
-            panic!(
-                "Failed to join async save_json for file: {:?}. Err: {err}",
-                Self::PATH
-            )
-        });
+        let json_bytes = self.to_versioned_vec();
+        let res = tokio::task::spawn_blocking(move || save_file_atomic(Self::PATH, &json_bytes))
+            .await
+            .map_err(std::io::Error::other)
+            .and_then(|res| res);
+        if let Err(err) = res {
+            report_save_error(Self::PATH, err);
+        }
     }
 
+    /// a failed write is reported by [`report_save_error`], the old file is kept
     fn save_json_sync(&self) {
-        let json_bytes = serde_json::to_vec(&self).unwrap_or_else(|err| {
-            panic!(
-                "Failed to serialize struct: {:?} to JSON. Err: {err}",
-                std::any::type_name::<Self>(),
-            )
-        });
-        save_file_with_lock(Self::PATH, &json_bytes);
+        let json_bytes = self.to_versioned_vec();
+        if let Err(err) = save_file_atomic(Self::PATH, &json_bytes) {
+            report_save_error(Self::PATH, err);
+        }
     }
 }
 
-/// Saves the provided data to the specified file path with file locking.
+/// Saves the provided data to the specified file path atomically.
 ///
-/// Ensures that the parent directory exists before writing. Locks the file for exclusive access
-/// during the write operation to prevent concurrent writes.
+/// Ensures that the parent directory exists before writing. The data is written and synced to a
+/// temp file in the same directory first, then renamed over the target, so a crash mid-write
+/// leaves either the old or the new file, never a truncated one.
 ///
 /// # Arguments
 /// * `path` - The file path to write to.
 /// * `data` - The byte slice to write.
 ///
-/// # Panics
-/// - If creating directories, creating the file, or writing fails.
-///
 /// # Errors
-/// - Logs an error if file locking fails, but does not panic.
-pub fn save_file_with_lock(path: impl AsRef<Path>, data: &[u8]) {
+/// - If creating directories, writing or renaming the temp file fails, the temp file is removed.
+pub fn save_file_atomic(path: impl AsRef<Path>, data: &[u8]) -> std::io::Result<()> {
+    static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
     let path = path.as_ref();
     if let Some(parent) = Path::new(path).parent() {
-        fs::create_dir_all(parent).unwrap();
+        fs::create_dir_all(parent)?;
+    }
+    // unique per writer, so concurrent saves of the same path won't share a temp file
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        path.file_name().unwrap_or_default().to_string_lossy(),
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let write = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })
+}
+
+/// move the broken file aside as `<path>.corrupted.<timestamp>` and report it over the webhook logger
+pub fn quarantine_file(path: impl AsRef<Path>, reason: impl Display) {
+    let path = path.as_ref();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let quarantined = path.with_file_name(format!(
+        "{}.corrupted.{now}",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    let msg = match fs::rename(path, &quarantined) {
+        Ok(_) => format!("Quarantined broken file: {path:?} -> {quarantined:?}\n{reason}"),
+        Err(err) => format!("Failed to quarantine broken file: {path:?}, Err: {err}\n{reason}"),
+    };
+    error!("{msg}");
+    if let Some(webhook_tx) = crate::WEBHOOK_TX.get() {
+        let _ = webhook_tx.send(msg);
+    }
+}
+
+/// log the failed save and report it over the webhook logger, the data stays in memory and the
+/// next save retries it
+fn report_save_error(path: &str, err: std::io::Error) {
+    let msg = format!("Failed to write file: {path:?}, Err: {err}");
+    error!("{msg}");
+    if let Some(webhook_tx) = crate::WEBHOOK_TX.get() {
+        let _ = webhook_tx.send(msg);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::{Deserialize, Serialize};
+    use serde_json::{Value, json};
+
+    use super::{LoadSaveFromJson, Migration, save_file_atomic};
+
+    /// version 0: `{"count": 1}`, version 1: `{"counts": [1]}`
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Counter {
+        counts: Vec<u64>,
     }
-    let mut file = std::fs::File::create(path)
-        .unwrap_or_else(|err| panic!("failed to create file: {path:?}, Err: {err}"));
-    if let Err(err) = file.lock() {
-        error!("Failed to lock file: {path:?} for writing. Err: {err}");
-        return;
+
+    impl LoadSaveFromJson for Counter {
+        const PATH: &'static str = "./user_data/test_counter.json";
+        const MIGRATIONS: &'static [Migration] = &[|mut value| {
+            let count = value
+                .get_mut("count")
+                .map(Value::take)
+                .ok_or("missing count")?;
+            Ok(json!({ "counts": [count] }))
+        }];
     }
-    if let Err(err) = file.write_all(data) {
-        panic!("Failed to write JSON to file: {path:?}, Err: {err}");
+
+    #[test]
+    fn legacy_file_gets_migrated() {
+        let (counter, migrated) = Counter::from_versioned_str(r#"{"count": 3}"#).unwrap();
+        assert!(migrated);
+        assert_eq!(counter.counts, vec![3]);
+
+        let saved = String::from_utf8(counter.to_versioned_vec()).unwrap();
+        assert_eq!(saved, r#"{"version":1,"data":{"counts":[3]}}"#);
+        let (reloaded, migrated) = Counter::from_versioned_str(&saved).unwrap();
+        assert!(!migrated);
+        assert_eq!(reloaded, counter);
+
+        assert!(Counter::from_versioned_str(r#"{"version":0,"data":{}}"#).is_err());
+    }
+
+    #[test]
+    fn newer_file_is_an_error() {
+        assert!(Counter::from_versioned_str(r#"{"version":2,"data":{"counts":[3]}}"#).is_err());
+    }
+
+    #[test]
+    fn failed_write_is_an_error() {
+        let dir = std::env::temp_dir().join(format!("isac_atomic_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.json");
+        save_file_atomic(&path, b"{}").unwrap();
+        // a file where the parent dir should be
+        assert!(save_file_atomic(path.join("nested.json"), b"{}").is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"{}");
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
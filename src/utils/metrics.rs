@@ -0,0 +1,225 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{Context, Error, utils::IsacError};
+
+/// the process wide metrics, served in the Prometheus text format by [`crate::tasks::metrics_server`]
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::default);
+
+/// (name, help) of all the metrics, a metric must be listed here to be rendered
+const DESCRIPTIONS: [(&str, &str); 11] = [
+    ("isac_command_invocations_total", "Commands invoked"),
+    (
+        "isac_command_errors_total",
+        "Commands failed, by the error kind",
+    ),
+    (
+        "isac_command_duration_seconds",
+        "Time taken by the commands",
+    ),
+    (
+        "isac_upstream_requests_total",
+        "Requests sent by WowsApi, by the result",
+    ),
+    (
+        "isac_upstream_request_duration_seconds",
+        "Time taken by the WowsApi requests, retries included",
+    ),
+    (
+        "isac_render_duration_seconds",
+        "Time taken by rendering the images",
+    ),
+    ("isac_render_errors_total", "Images failed to render"),
+    (
+        "isac_render_cache_total",
+        "Lookups of the render cache, by the result",
+    ),
+    (
+        "isac_renderer_restarts_total",
+        "Restarts of the renderer by the supervisor",
+    ),
+    (
+        "isac_task_runs_total",
+        "Runs of the background tasks, by the result",
+    ),
+    (
+        "isac_task_duration_seconds",
+        "Time taken by the background tasks",
+    ),
+];
+
+/// the upper bounds of the histogram buckets, in seconds
+const BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 120.0];
+
+/// `[(label, value)]`
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Default)]
+struct Histogram {
+    /// not cumulative, one for each of [`BUCKETS`] and the last one for `+Inf`
+    buckets: [u64; BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    counters: Mutex<BTreeMap<(&'static str, Labels), u64>>,
+    histograms: Mutex<BTreeMap<(&'static str, Labels), Histogram>>,
+}
+
+impl Metrics {
+    pub fn inc(&self, name: &'static str, labels: &[(&'static str, &str)]) {
+        *self.counters.lock().entry((name, own(labels))).or_default() += 1;
+    }
+
+    pub fn observe(&self, name: &'static str, labels: &[(&'static str, &str)], value: Duration) {
+        let value = value.as_secs_f64();
+        let mut histograms = self.histograms.lock();
+        let histogram = histograms.entry((name, own(labels))).or_default();
+        let bucket = BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(BUCKETS.len());
+        histogram.buckets[bucket] += 1;
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+
+    /// a shortcut for the runs of the background tasks
+    pub fn task_run(&self, task: &str, ok: bool, took: Duration) {
+        let result = if ok { "ok" } else { "error" };
+        self.inc(
+            "isac_task_runs_total",
+            &[("task", task), ("result", result)],
+        );
+        self.observe("isac_task_duration_seconds", &[("task", task)], took);
+    }
+
+    /// the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let counters = self.counters.lock();
+        let histograms = self.histograms.lock();
+        let mut output = String::new();
+        for (name, help) in DESCRIPTIONS {
+            let is_histogram = name.ends_with("_seconds");
+            let kind = if is_histogram { "histogram" } else { "counter" };
+            let _ = writeln!(output, "# HELP {name} {help}\n# TYPE {name} {kind}");
+            if !is_histogram {
+                for ((_, labels), value) in counters.range(range_of(name)) {
+                    let _ = writeln!(output, "{name}{} {value}", format_labels(labels, None));
+                }
+                continue;
+            }
+            for ((_, labels), histogram) in histograms.range(range_of(name)) {
+                let mut cumulative = 0;
+                for (i, count) in histogram.buckets.iter().enumerate() {
+                    cumulative += count;
+                    let le = BUCKETS.get(i).map_or("+Inf".to_string(), f64::to_string);
+                    let labels = format_labels(labels, Some(&le));
+                    let _ = writeln!(output, "{name}_bucket{labels} {cumulative}");
+                }
+                let labels = format_labels(labels, None);
+                let _ = writeln!(output, "{name}_sum{labels} {}", histogram.sum);
+                let _ = writeln!(output, "{name}_count{labels} {}", histogram.count);
+            }
+        }
+        output
+    }
+}
+
+/// for [`poise::FrameworkOptions::pre_command`]
+pub async fn command_started(ctx: Context<'_>) {
+    let command = ctx.command().qualified_name.as_str();
+    METRICS.inc("isac_command_invocations_total", &[("command", command)]);
+    ctx.set_invocation_data(Instant::now()).await;
+}
+
+/// for [`poise::FrameworkOptions::post_command`] and the error handler, `error` is None if succeeded
+pub async fn command_finished(ctx: Context<'_>, error: Option<&Error>) {
+    let command = ctx.command().qualified_name.as_str();
+    if let Some(error) = error {
+        let kind = error
+            .downcast_ref::<IsacError>()
+            .map_or("Other", IsacError::kind);
+        METRICS.inc(
+            "isac_command_errors_total",
+            &[("command", command), ("kind", kind)],
+        );
+    }
+    // None if it failed before the command started, e.g. argument parsing
+    let started = ctx
+        .invocation_data::<Instant>()
+        .await
+        .map(|started| *started);
+    if let Some(started) = started {
+        METRICS.observe(
+            "isac_command_duration_seconds",
+            &[("command", command)],
+            started.elapsed(),
+        );
+    }
+}
+
+fn own(labels: &[(&'static str, &str)]) -> Labels {
+    labels.iter().map(|(k, v)| (*k, v.to_string())).collect()
+}
+
+/// all the keys of the metric
+fn range_of(name: &'static str) -> std::ops::RangeInclusive<(&'static str, Labels)> {
+    // "~" is bigger than any label name, so the range covers all labels
+    (name, vec![])..=(name, vec![("~", String::new())])
+}
+
+/// `{a="1",le="0.5"}`, empty if no labels
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let pairs = labels
+        .iter()
+        .map(|(k, v)| (*k, v.as_str()))
+        .chain(le.map(|le| ("le", le)))
+        .map(|(k, v)| format!(r#"{k}="{}""#, v.replace('\\', r"\\").replace('"', r#"\""#)))
+        .collect::<Vec<_>>();
+    match pairs.is_empty() {
+        true => String::new(),
+        false => format!("{{{}}}", pairs.join(",")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::Metrics;
+
+    #[test]
+    fn render_prometheus_text() {
+        let metrics = Metrics::default();
+        metrics.inc("isac_command_invocations_total", &[("command", "wws")]);
+        metrics.inc("isac_command_invocations_total", &[("command", "wws")]);
+        metrics.observe(
+            "isac_command_duration_seconds",
+            &[("command", "wws")],
+            Duration::from_millis(300),
+        );
+        let output = metrics.render();
+        assert!(output.contains("isac_command_invocations_total{command=\"wws\"} 2\n"));
+        assert!(
+            output
+                .contains("isac_command_duration_seconds_bucket{command=\"wws\",le=\"0.25\"} 0\n")
+        );
+        assert!(
+            output.contains("isac_command_duration_seconds_bucket{command=\"wws\",le=\"0.5\"} 1\n")
+        );
+        assert!(
+            output
+                .contains("isac_command_duration_seconds_bucket{command=\"wws\",le=\"+Inf\"} 1\n")
+        );
+        assert!(output.contains("isac_command_duration_seconds_count{command=\"wws\"} 1\n"));
+    }
+}
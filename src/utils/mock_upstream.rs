@@ -0,0 +1,90 @@
+//! a local stand-in of the upstreams, replaying the recorded json in `tests/fixtures`
+//!
+//! the request `GET /vortex/asia/api/accounts/1/` is answered with
+//! - `tests/fixtures/vortex/asia/api/accounts/1.json` in 200
+//! - or `tests/fixtures/vortex/asia/api/accounts/1.<status>.json` in that status
+//! - or `tests/fixtures/vortex/asia/api/accounts/_.json`, which matches any last segment
+//!
+//! the query string is ignored, anything else is a 404
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    thread,
+};
+
+use once_cell::sync::Lazy;
+
+use crate::structs::Region;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// start the server if it's not, and redirect all the [`Region`] urls to it
+pub fn start() {
+    static SERVER: Lazy<()> = Lazy::new(|| {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind the mock upstream");
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                thread::spawn(move || serve(stream));
+            }
+        });
+        Region::override_base_url(Some(base));
+    });
+    Lazy::force(&SERVER);
+}
+
+fn serve(mut stream: TcpStream) {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // GET requests have no body, just skip the headers
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok_and(|len| len > 2) {
+        header.clear();
+    }
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split('?').next().unwrap_or_default();
+    let (status, body) = find_fixture(path).unwrap_or_else(|| {
+        (
+            404,
+            format!(r#"{{"status":"error","error":"no fixture for {path}"}}"#),
+        )
+    });
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+/// (status, body) of the fixture matching the path
+fn find_fixture(path: &str) -> Option<(u16, String)> {
+    let path = path.trim_matches('/');
+    let (dir, last) = path.rsplit_once('/').unwrap_or(("", path));
+    let dir = Path::new(FIXTURES).join(dir);
+    for name in [last, "_"] {
+        if let Ok(body) = fs::read_to_string(dir.join(format!("{name}.json"))) {
+            return Some((200, body));
+        }
+        // <name>.<status>.json
+        let with_status = fs::read_dir(&dir).ok()?.flatten().find_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let status = file_name
+                .strip_prefix(name)?
+                .strip_prefix('.')?
+                .strip_suffix(".json")?
+                .parse()
+                .ok()?;
+            Some((status, fs::read_to_string(entry.path()).ok()?))
+        });
+        if with_status.is_some() {
+            return with_status;
+        }
+    }
+    None
+}
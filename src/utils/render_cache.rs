@@ -0,0 +1,392 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use bytes::Bytes;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+use crate::template_data::RendererConfig;
+
+/// the rendered images, so the same data is not screenshotted twice, see [`RenderCache`]
+pub static RENDER_CACHE: Lazy<RenderCache> = Lazy::new(|| {
+    let config = RendererConfig::get();
+    RenderCache::new(
+        "./cache/renders",
+        Duration::from_secs(config.cache_ttl_secs),
+        config.cache_max_mb * 1024 * 1024,
+    )
+});
+
+struct Entry {
+    size: u64,
+    created: SystemTime,
+}
+
+/// the images on disk named by the hash of the template and its data, bounded by the TTL and the total size
+///
+/// the least recently used ones are removed first when it's full, the index is rebuilt from the files on restart
+pub struct RenderCache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_bytes: u64,
+    /// built from the files on disk on first use, see [`RenderCache::load`]
+    index: OnceCell<Mutex<Index>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct Index {
+    entries: LruCache<String, Entry>,
+    bytes: u64,
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Self {
+            entries: LruCache::unbounded(),
+            bytes: 0,
+        }
+    }
+}
+
+impl Index {
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.pop(key)?;
+        self.bytes -= entry.size;
+        Some(entry)
+    }
+}
+
+#[derive(Debug)]
+pub struct RenderCacheStats {
+    pub ttl: Duration,
+    pub entries: usize,
+    pub bytes: u64,
+    pub max_bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl RenderCache {
+    pub fn new(dir: impl AsRef<Path>, ttl: Duration, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            ttl,
+            max_bytes,
+            index: OnceCell::new(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// the version of the templates in the dir, a hash of every file in it with the crate version,
+    /// so editing a `.hbs` or a partial, or upgrading the native renderer, won't serve old images
+    pub fn templates_version(dir: impl AsRef<Path>) -> String {
+        let mut files = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.is_file())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        files.sort();
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        for path in files {
+            hasher.update([0]);
+            hasher.update(path.file_name().unwrap_or_default().as_encoded_bytes());
+            hasher.update([0]);
+            hasher.update(std::fs::read(&path).unwrap_or_default());
+        }
+        hasher
+            .finalize()
+            .iter()
+            .take(8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// the key of the template with the data in json
+    pub fn key(template: &str, json: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(template.as_bytes());
+        hasher.update([0]);
+        hasher.update(json);
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.png"))
+    }
+
+    /// index the files left by the last run, call it at startup so the first render won't wait
+    pub async fn load(&self) {
+        self.index().await;
+    }
+
+    async fn index(&self) -> &Mutex<Index> {
+        self.index
+            .get_or_init(|| async {
+                let (dir, ttl, max_bytes) = (self.dir.clone(), self.ttl, self.max_bytes);
+                let index = tokio::task::spawn_blocking(move || Self::scan(&dir, ttl, max_bytes))
+                    .await
+                    .unwrap_or_else(|err| {
+                        warn!("failed to index the render cache: {err}");
+                        Index::default()
+                    });
+                Mutex::new(index)
+            })
+            .await
+    }
+
+    /// run `f` with the index, the files on disk are indexed on the first call
+    async fn with_index<R>(&self, f: impl FnOnce(&mut Index) -> R) -> R {
+        f(&mut self.index().await.lock())
+    }
+
+    /// index the files in `dir`, the oldest one is the least recently used
+    ///
+    /// the expired ones, the oldest ones over the limit and the temp files of an interrupted
+    /// write are deleted, since nothing would remove them later
+    fn scan(dir: &Path, ttl: Duration, max_bytes: u64) -> Index {
+        let mut files = vec![];
+        let mut removed = 0;
+        for file in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = file.path();
+            let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let created = file.metadata().ok().and_then(|meta| {
+                let created = meta.modified().ok()?;
+                Some((meta.len(), created))
+            });
+            match (path.extension().and_then(|ext| ext.to_str()), created) {
+                (Some("png"), Some((size, created)))
+                    if created.elapsed().unwrap_or_default() <= ttl =>
+                {
+                    files.push((key.to_string(), size, created));
+                }
+                (Some("png" | "tmp"), _) => {
+                    removed += std::fs::remove_file(&path).is_ok() as usize;
+                }
+                _ => (),
+            }
+        }
+        files.sort_by_key(|(_, _, created)| *created);
+        let mut bytes: u64 = files.iter().map(|(_, size, _)| size).sum();
+        let mut index = Index::default();
+        for (key, size, created) in files {
+            if bytes > max_bytes {
+                bytes -= size;
+                removed += std::fs::remove_file(dir.join(format!("{key}.png"))).is_ok() as usize;
+                continue;
+            }
+            index.bytes += size;
+            index.entries.put(key, Entry { size, created });
+        }
+        info!(
+            "render cache indexed: {} images, {removed} removed",
+            index.entries.len()
+        );
+        index
+    }
+
+    /// the cached image if it's not expired
+    pub async fn get(&self, key: &str) -> Option<Bytes> {
+        let fresh = self
+            .with_index(|index| {
+                let expired = index
+                    .entries
+                    .get(key)
+                    .map(|entry| entry.created.elapsed().unwrap_or_default() > self.ttl)?;
+                if expired {
+                    index.remove(key);
+                }
+                Some(!expired)
+            })
+            .await;
+        let img = match fresh {
+            Some(true) => match tokio::fs::read(self.path(key)).await {
+                Ok(img) => Some(Bytes::from(img)),
+                Err(_) => {
+                    self.with_index(|index| index.remove(key)).await;
+                    None
+                }
+            },
+            Some(false) => {
+                let _ = tokio::fs::remove_file(self.path(key)).await;
+                None
+            }
+            None => None,
+        };
+        match img.is_some() {
+            true => self.hits.fetch_add(1, Ordering::Relaxed),
+            false => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        img
+    }
+
+    /// save the image, removing the least recently used ones if it's full
+    pub async fn put(&self, key: &str, img: &Bytes) {
+        let size = img.len() as u64;
+        if size > self.max_bytes {
+            return;
+        }
+        if let Err(err) = self.write(key, img).await {
+            warn!("failed to cache the render {key}: {err}");
+            return;
+        }
+        let evicted = self
+            .with_index(|index| {
+                index.remove(key);
+                index.bytes += size;
+                let created = SystemTime::now();
+                index.entries.put(key.to_string(), Entry { size, created });
+                let mut evicted = vec![];
+                while index.bytes > self.max_bytes {
+                    let Some((key, entry)) = index.entries.pop_lru() else {
+                        break;
+                    };
+                    index.bytes -= entry.size;
+                    evicted.push(key);
+                }
+                evicted
+            })
+            .await;
+        for key in evicted {
+            let _ = tokio::fs::remove_file(self.path(&key)).await;
+        }
+    }
+
+    /// write to a temp file first, so a crash never leaves a broken image behind
+    async fn write(&self, key: &str, img: &Bytes) -> std::io::Result<()> {
+        static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // unique per writer, so two renders of the same key won't share a temp file
+        let temp = self.dir.join(format!(
+            ".{key}.{}.{}.tmp",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let res = match tokio::fs::write(&temp, img).await {
+            Ok(()) => tokio::fs::rename(&temp, self.path(key)).await,
+            Err(err) => Err(err),
+        };
+        if res.is_err() {
+            let _ = tokio::fs::remove_file(&temp).await;
+        }
+        res
+    }
+
+    pub async fn stats(&self) -> RenderCacheStats {
+        let (entries, bytes) = self
+            .with_index(|index| (index.entries.len(), index.bytes))
+            .await;
+        RenderCacheStats {
+            ttl: self.ttl,
+            entries,
+            bytes,
+            max_bytes: self.max_bytes,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// remove all the cached images, e.g. after the templates are changed
+    pub async fn flush(&self) {
+        let keys: Vec<_> = self
+            .with_index(|index| {
+                index.bytes = 0;
+                let keys = index.entries.iter().map(|(key, _)| key.clone()).collect();
+                index.entries.clear();
+                keys
+            })
+            .await;
+        for key in keys {
+            let _ = tokio::fs::remove_file(self.path(&key)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use super::RenderCache;
+
+    #[tokio::test]
+    async fn bounded_by_size_and_ttl() {
+        let dir = std::env::temp_dir().join(format!("isac_render_cache_{}", std::process::id()));
+        let cache = RenderCache::new(&dir, Duration::from_secs(60), 10);
+        let (a, b, c) = (
+            RenderCache::key("leaderboard", b"{\"a\":1}"),
+            RenderCache::key("kleaderboard", b"{\"a\":1}"),
+            RenderCache::key("leaderboard", b"{\"a\":2}"),
+        );
+        assert_ne!(a, b);
+        assert!(cache.get(&a).await.is_none());
+
+        cache.put(&a, &Bytes::from_static(b"aaaa")).await;
+        cache.put(&b, &Bytes::from_static(b"bbbb")).await;
+        // touch `a`, so `b` is the least recently used one
+        assert_eq!(cache.get(&a).await.unwrap(), "aaaa");
+        cache.put(&c, &Bytes::from_static(b"cccc")).await;
+        assert!(cache.get(&b).await.is_none());
+        assert_eq!(cache.stats().await.bytes, 8);
+
+        // the index is rebuilt from the files, without the temp file of an interrupted write
+        let temp = dir.join(format!(".{b}.{}.0.tmp", std::process::id()));
+        std::fs::write(&temp, b"bb").unwrap();
+        let reopened = RenderCache::new(&dir, Duration::from_secs(60), 10);
+        assert_eq!(reopened.get(&c).await.unwrap(), "cccc");
+        assert_eq!(reopened.stats().await.entries, 2);
+        assert!(!temp.exists());
+
+        // and the ones over a smaller limit are removed
+        let smaller = RenderCache::new(&dir, Duration::from_secs(60), 4);
+        smaller.load().await;
+        assert_eq!(smaller.stats().await.bytes, 4);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        // the expired ones as well
+        let expired = RenderCache::new(&dir, Duration::ZERO, 10);
+        expired.load().await;
+        assert_eq!(expired.stats().await.entries, 0);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        cache.flush().await;
+        assert_eq!(cache.stats().await.entries, 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn templates_version_follows_the_files() {
+        let dir = std::env::temp_dir().join(format!("isac_templates_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("overall.hbs"), "a").unwrap();
+        let before = RenderCache::templates_version(&dir);
+        assert_eq!(before, RenderCache::templates_version(&dir));
+
+        // a partial counts as much as the template itself
+        std::fs::write(dir.join("class_tier.hbs"), "b").unwrap();
+        let after = RenderCache::templates_version(&dir);
+        assert_ne!(before, after);
+        std::fs::write(dir.join("class_tier.hbs"), "c").unwrap();
+        assert_ne!(after, RenderCache::templates_version(&dir));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
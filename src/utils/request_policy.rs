@@ -0,0 +1,238 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::{structs::Region, utils::LoadSaveFromJson};
+
+/// the hosts [`super::wws_api::WowsApi`] talks to, each one has its own rate limit
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, strum::IntoStaticStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Upstream {
+    Vortex,
+    Api,
+    Clans,
+    Kokomi,
+    WowsNumbers,
+}
+
+impl Upstream {
+    /// which upstream the url belongs to, None for the others, e.g. the profile site
+    pub fn of(url: &Url) -> Option<Self> {
+        if let Some(upstream) = Region::overridden_upstream(url) {
+            return match upstream.as_str() {
+                "vortex" => Some(Self::Vortex),
+                "api" => Some(Self::Api),
+                "clan" => Some(Self::Clans),
+                "kokomi" => Some(Self::Kokomi),
+                _ => None,
+            };
+        }
+        let host = url.host_str()?;
+        let is_kokomi = || {
+            [Region::Asia, Region::Na, Region::Eu]
+                .iter()
+                .any(|region| region.kokomi_host() == host)
+        };
+        Some(if host.contains("wows-numbers") {
+            Self::WowsNumbers
+        } else if host.starts_with("vortex.") {
+            Self::Vortex
+        } else if host.starts_with("api.") {
+            Self::Api
+        } else if host.starts_with("clans.") {
+            Self::Clans
+        } else if is_kokomi() {
+            Self::Kokomi
+        } else {
+            None?
+        })
+    }
+}
+
+/// a token bucket, `per_second` tokens are refilled every second, up to `burst`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+/// how the requests to the upstreams are retried and rate limited, edit the json to change it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RequestPolicy {
+    /// the max attempts of a request, including the first one
+    pub max_attempts: u32,
+    /// the first retry waits around X ms, doubled on each retry
+    pub base_delay_ms: u64,
+    /// the cap of the retry delay
+    pub max_delay_ms: u64,
+    /// upstreams not listed here are not limited
+    pub rate_limits: HashMap<Upstream, RateLimit>,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        let limit = |per_second, burst| RateLimit { per_second, burst };
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 300,
+            max_delay_ms: 3000,
+            rate_limits: HashMap::from([
+                (Upstream::Vortex, limit(20.0, 40.0)),
+                // shared application_id, WG allows 20 rps for a server app
+                (Upstream::Api, limit(15.0, 20.0)),
+                (Upstream::Clans, limit(10.0, 20.0)),
+                (Upstream::Kokomi, limit(5.0, 10.0)),
+                (Upstream::WowsNumbers, limit(2.0, 4.0)),
+            ]),
+        }
+    }
+}
+
+impl LoadSaveFromJson for RequestPolicy {
+    const PATH: &'static str = "./user_data/request_policy.json";
+}
+
+impl RequestPolicy {
+    /// the policy loaded from [`RequestPolicy::PATH`]
+    ///
+    /// the tests run against [`super::mock_upstream`], so no rate limits and almost no delays there
+    pub fn get() -> &'static Self {
+        static POLICY: Lazy<RequestPolicy> = Lazy::new(|| match cfg!(test) {
+            true => RequestPolicy {
+                base_delay_ms: 1,
+                max_delay_ms: 1,
+                rate_limits: HashMap::new(),
+                ..Default::default()
+            },
+            false => RequestPolicy::load_json_sync(),
+        });
+        &POLICY
+    }
+
+    /// wait until the url's upstream has a token
+    pub async fn throttle(url: &Url) {
+        static BUCKETS: Lazy<HashMap<Upstream, TokenBucket>> = Lazy::new(|| {
+            RequestPolicy::get()
+                .rate_limits
+                .iter()
+                .map(|(upstream, limit)| (*upstream, TokenBucket::new(*limit)))
+                .collect()
+        });
+        if let Some(bucket) = Upstream::of(url).and_then(|upstream| BUCKETS.get(&upstream)) {
+            bucket.acquire().await;
+        }
+    }
+
+    /// the delay before the retry, `attempt` starts from 0
+    ///
+    /// jittered between 50% ~ 100% so the concurrent requests won't retry at the same time
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay_ms
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_delay_ms);
+        Duration::from_millis(rand::random_range(delay / 2..=delay))
+    }
+
+    /// 5xx, 429 and timeouts are worth retrying
+    pub fn should_retry_reqwest(err: &reqwest::Error) -> bool {
+        err.is_timeout()
+            || err.is_connect()
+            || err
+                .status()
+                .is_some_and(|status| status.is_server_error() || status.as_u16() == 429)
+    }
+
+    /// see [`RequestPolicy::should_retry_reqwest`]
+    pub fn should_retry_ureq(err: &ureq::Error) -> bool {
+        match err {
+            ureq::Error::StatusCode(code) => *code >= 500 || *code == 429,
+            ureq::Error::Timeout(_) | ureq::Error::Io(_) | ureq::Error::ConnectionFailed => true,
+            _ => false,
+        }
+    }
+}
+
+struct TokenBucket {
+    limit: RateLimit,
+    /// (tokens, last refilled at)
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new((limit.burst, Instant::now())),
+        }
+    }
+
+    /// take a token, or the time to wait for the next one
+    fn try_take(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock();
+        let now = Instant::now();
+        let refilled = now.duration_since(state.1).as_secs_f64() * self.limit.per_second;
+        *state = ((state.0 + refilled).min(self.limit.burst), now);
+        if state.0 >= 1.0 {
+            state.0 -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - state.0) / self.limit.per_second,
+            ))
+        }
+    }
+
+    async fn acquire(&self) {
+        while let Err(wait) = self.try_take() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use reqwest::Url;
+
+    use super::{RateLimit, TokenBucket, Upstream};
+
+    #[test]
+    fn upstream_of_url() {
+        let of = |url: &str| Upstream::of(&Url::parse(url).unwrap());
+        assert_eq!(
+            of("https://vortex.worldofwarships.asia/api/accounts/1/"),
+            Some(Upstream::Vortex)
+        );
+        assert_eq!(
+            of("https://api.wows-numbers.com/personal/rating/expected/json/"),
+            Some(Upstream::WowsNumbers)
+        );
+        assert_eq!(
+            of("http://43.160.202.239:8000/docs"),
+            Some(Upstream::Kokomi)
+        );
+        assert_eq!(of("https://profile.worldofwarships.eu/"), None);
+    }
+
+    #[test]
+    fn token_bucket_limits_burst() {
+        let bucket = TokenBucket::new(RateLimit {
+            per_second: 10.0,
+            burst: 2.0,
+        });
+        assert!(bucket.try_take().is_ok());
+        assert!(bucket.try_take().is_ok());
+        let wait = bucket.try_take().unwrap_err();
+        assert!(wait <= Duration::from_millis(100));
+    }
+}
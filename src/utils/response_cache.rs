@@ -0,0 +1,186 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tokio::sync::OnceCell;
+
+use crate::{
+    structs::{Clan, PartialClan, Player, Region, ShipStatsCollection},
+    utils::IsacError,
+};
+
+/// the vortex responses shared between commands and buttons, see [`TtlCache`]
+pub static RESPONSE_CACHE: Lazy<ResponseCache> = Lazy::new(|| ResponseCache {
+    player: TtlCache::new("player", Duration::from_secs(60)),
+    player_clan: TtlCache::new("player_clan", Duration::from_secs(300)),
+    ships: TtlCache::new("ships", Duration::from_secs(60)),
+    ships_extra: TtlCache::new("ships_extra", Duration::from_secs(60)),
+    clan: TtlCache::new("clan", Duration::from_secs(300)),
+});
+
+/// one [`TtlCache`] per endpoint, so the key is (region, endpoint, id)
+pub struct ResponseCache {
+    /// [`super::wws_api::WowsApi::player_personal_data`]
+    pub player: TtlCache<Player>,
+    /// [`super::wws_api::WowsApi::player_clan`]
+    pub player_clan: TtlCache<Option<PartialClan>>,
+    /// [`super::wws_api::WowsApi::statistics_of_player_ships`] of all ships
+    pub ships: TtlCache<ShipStatsCollection>,
+    /// the same with `extra_modes`, kept apart since it has more modes
+    pub ships_extra: TtlCache<ShipStatsCollection>,
+    /// [`super::wws_api::WowsApi::clan_stats`]
+    pub clan: TtlCache<Clan>,
+}
+
+impl ResponseCache {
+    pub fn stats(&self) -> [CacheStats; 5] {
+        [
+            self.player.stats(),
+            self.player_clan.stats(),
+            self.ships.stats(),
+            self.ships_extra.stats(),
+            self.clan.stats(),
+        ]
+    }
+
+    pub fn flush(&self) {
+        self.player.flush();
+        self.player_clan.flush();
+        self.ships.flush();
+        self.ships_extra.flush();
+        self.clan.flush();
+    }
+}
+
+#[derive(Debug)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub ttl: Duration,
+    /// the cached responses, including the expired ones not pruned yet
+    pub entries: usize,
+    /// the requests still waiting for the upstream
+    pub in_flight: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// empty if the request is in flight, or failed
+type CacheCell<V> = Arc<OnceCell<(Instant, V)>>;
+
+/// an async cache with TTL, errors are not cached
+///
+/// identical requests in flight are de-duplicated, the later ones wait for the first one
+pub struct TtlCache<V> {
+    name: &'static str,
+    ttl: Duration,
+    entries: Mutex<HashMap<(Region, u64), CacheCell<V>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<V: Clone> TtlCache<V> {
+    pub fn new(name: &'static str, ttl: Duration) -> Self {
+        Self {
+            name,
+            ttl,
+            entries: Default::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// return the cached one if it's not expired, otherwise run the `fetch`
+    pub async fn get_or_fetch<F>(&self, region: Region, id: u64, fetch: F) -> Result<V, IsacError>
+    where
+        F: Future<Output = Result<V, IsacError>>,
+    {
+        let cell = {
+            let mut entries = self.entries.lock();
+            let is_fresh = |cell: &OnceCell<(Instant, V)>| {
+                cell.get()
+                    .is_none_or(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            };
+            if !entries.contains_key(&(region, id)) {
+                // only idle cells can be dropped, someone might be waiting for the others
+                entries.retain(|_, cell| is_fresh(cell) || Arc::strong_count(cell) > 1);
+            }
+            let cell = entries.entry((region, id)).or_default();
+            if !is_fresh(cell) {
+                *cell = Arc::default();
+            }
+            Arc::clone(cell)
+        };
+        if cell.initialized() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        cell.get_or_try_init(|| async { Ok((Instant::now(), fetch.await?)) })
+            .await
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries.lock();
+        let in_flight = entries.values().filter(|cell| !cell.initialized()).count();
+        CacheStats {
+            name: self.name,
+            ttl: self.ttl,
+            entries: entries.len() - in_flight,
+            in_flight,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// drop all the cached responses, the requests in flight are not affected
+    pub fn flush(&self) {
+        self.entries.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicU64, Ordering},
+        time::Duration,
+    };
+
+    use super::TtlCache;
+    use crate::structs::Region;
+
+    #[tokio::test]
+    async fn identical_requests_are_fetched_once() {
+        let cache = TtlCache::<u64>::new("test", Duration::from_secs(60));
+        let fetched = AtomicU64::new(0);
+        let fetch = || async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(fetched.fetch_add(1, Ordering::Relaxed))
+        };
+        let (a, b) = tokio::join!(
+            cache.get_or_fetch(Region::Asia, 1, fetch()),
+            cache.get_or_fetch(Region::Asia, 1, fetch())
+        );
+        assert_eq!((a.unwrap(), b.unwrap()), (0, 0));
+        assert_eq!(
+            cache.get_or_fetch(Region::Asia, 1, fetch()).await.unwrap(),
+            0
+        );
+        assert_eq!(cache.get_or_fetch(Region::Na, 1, fetch()).await.unwrap(), 1);
+
+        cache.flush();
+        assert_eq!(
+            cache.get_or_fetch(Region::Asia, 1, fetch()).await.unwrap(),
+            2
+        );
+        assert_eq!(fetched.load(Ordering::Relaxed), 3);
+    }
+}
@@ -0,0 +1,426 @@
+use std::{
+    env,
+    fmt::Display,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use rusqlite::{Connection, OptionalExtension, params};
+use tracing::{error, info, warn};
+
+use crate::{
+    Error,
+    structs::{PartialPlayer, PlayerSnapshots, Region, ShipStatsCollection},
+    utils::{quarantine_file, save_file_atomic},
+};
+
+const JSON_ROOT: &str = "./recent_DB/players/";
+const DEFAULT_SQLITE_PATH: &str = "./recent_DB/snapshots.sqlite3";
+
+/// where the [`PlayerSnapshots`] live, all methods are blocking
+pub trait SnapshotStore: Send + Sync {
+    /// load the player's snapshots, None if he is not inside
+    fn load(&self, player: PartialPlayer) -> Result<Option<PlayerSnapshots>, Error>;
+
+    /// only load the latest snapshot, None if he is not inside
+    fn latest(&self, player: PartialPlayer) -> Result<Option<ShipStatsCollection>, Error> {
+        Ok(self
+            .load(player)?
+            .and_then(|snapshots| snapshots.latest_snapshot()))
+    }
+
+    fn save(&self, snapshots: &PlayerSnapshots) -> Result<(), Error>;
+
+    fn delete(&self, player: PartialPlayer) -> Result<(), Error>;
+
+    /// all the players in the store of the given region
+    fn players(&self, region: Region) -> Result<Vec<PartialPlayer>, Error>;
+}
+
+/// the store chosen by `SNAPSHOT_STORE` in env, `json` (default) or `sqlite`
+///
+/// opened in `main` at startup, an Err here (e.g. sqlite failed to open) is retried on the next call
+pub fn store() -> Result<&'static dyn SnapshotStore, Error> {
+    static STORE: OnceCell<Box<dyn SnapshotStore>> = OnceCell::new();
+    STORE
+        .get_or_try_init(|| {
+            Ok::<Box<dyn SnapshotStore>, Error>(
+                match env::var("SNAPSHOT_STORE")
+                    .unwrap_or_default()
+                    .to_lowercase()
+                    .as_str()
+                {
+                    "sqlite" => {
+                        let path = sqlite_path();
+                        info!("Using sqlite snapshot store: {path:?}");
+                        Box::new(SqliteStore::open(&path).map_err(|err| {
+                            format!("Failed to open sqlite: {path:?}, Err: {err}")
+                        })?)
+                    }
+                    "" | "json" => Box::new(JsonStore::default()),
+                    other => {
+                        warn!("Unknown SNAPSHOT_STORE: `{other}`, using json instead");
+                        Box::new(JsonStore::default())
+                    }
+                },
+            )
+        })
+        .map(|store| store.as_ref())
+}
+
+/// the sqlite file path, `SNAPSHOT_DB_PATH` in env
+pub fn sqlite_path() -> PathBuf {
+    env::var("SNAPSHOT_DB_PATH")
+        .unwrap_or(DEFAULT_SQLITE_PATH.to_string())
+        .into()
+}
+
+/// import all the players in the json tree into the sqlite, return the imported count
+pub fn migrate_json_to_sqlite(json: &JsonStore, sqlite: &SqliteStore) -> Result<usize, Error> {
+    let mut count = 0;
+    for region in [Region::Asia, Region::Na, Region::Eu] {
+        for player in json.players(region)? {
+            if let Some(snapshots) = json.load(player)? {
+                sqlite.save(&snapshots)?;
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// one json file per player, `./recent_DB/players/<region>/<uid>.json`
+pub struct JsonStore {
+    root: PathBuf,
+}
+
+impl Default for JsonStore {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from(JSON_ROOT),
+        }
+    }
+}
+
+impl JsonStore {
+    /// get player's file path
+    fn get_path(&self, player: PartialPlayer) -> PathBuf {
+        let mut path = self.get_dir(player.region);
+        path.push(format!("{}.json", player.uid));
+        path
+    }
+
+    /// get the region's folder path
+    fn get_dir(&self, region: Region) -> PathBuf {
+        self.root.join(region.lower())
+    }
+}
+
+impl SnapshotStore for JsonStore {
+    fn load(&self, player: PartialPlayer) -> Result<Option<PlayerSnapshots>, Error> {
+        // std::fs::File::open() is as fast as path.exists()
+        let path = self.get_path(player);
+        let Ok(file) = std::fs::File::open(&path) else {
+            return Ok(None);
+        };
+        let json_str = std::io::read_to_string(file)?;
+        match serde_json::from_str::<PlayerSnapshots>(&json_str) {
+            Ok(mut snapshots) => {
+                snapshots.player = player;
+                Ok(Some(snapshots))
+            }
+            // a broken file is treated as a new player, instead of failing every command forever
+            Err(err) => {
+                quarantine_file(&path, format!("player: {player:?}, Err: {err}"));
+                Ok(None)
+            }
+        }
+    }
+
+    fn save(&self, snapshots: &PlayerSnapshots) -> Result<(), Error> {
+        let json_bytes = serde_json::to_vec(snapshots)?;
+        save_file_atomic(self.get_path(snapshots.player), &json_bytes)?;
+        Ok(())
+    }
+
+    fn delete(&self, player: PartialPlayer) -> Result<(), Error> {
+        Ok(std::fs::remove_file(self.get_path(player))?)
+    }
+
+    fn players(&self, region: Region) -> Result<Vec<PartialPlayer>, Error> {
+        let Ok(dir) = std::fs::read_dir(self.get_dir(region)) else {
+            return Ok(vec![]);
+        };
+        Ok(dir
+            .flatten()
+            // 12345678.json
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .and_then(|uid| uid.parse().ok())
+            })
+            .map(|uid| PartialPlayer { region, uid })
+            .collect())
+    }
+}
+
+/// an embedded sqlite, one row per player with the same payload as [`JsonStore`]'s file
+///
+/// the latest snapshot is kept in full beside it, so it can be loaded without applying the deltas
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+/// the schema version in `PRAGMA user_version`, bump it with a migration in [`SqliteStore::open`]
+/// when the tables change
+const SQLITE_VERSION: i64 = 0;
+
+impl SqliteStore {
+    /// open or create the database with given path
+    pub fn open(path: &PathBuf) -> Result<Self, Error> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        // wait for the other connections, e.g. the `migrate_snapshots` command, instead of failing
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS player_snapshots (
+                region TEXT NOT NULL,
+                uid INTEGER NOT NULL,
+                data TEXT NOT NULL,
+                latest TEXT,
+                PRIMARY KEY (region, uid)
+            );
+            CREATE TABLE IF NOT EXISTS quarantine (
+                region TEXT NOT NULL,
+                uid INTEGER NOT NULL,
+                data TEXT NOT NULL,
+                error TEXT NOT NULL,
+                quarantined_at INTEGER NOT NULL
+            );",
+        )?;
+        let store = Self {
+            conn: Mutex::new(conn),
+        };
+        let version: i64 = store
+            .conn
+            .lock()
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SQLITE_VERSION {
+            return Err(format!(
+                "The sqlite snapshot store is version {version}, newer than {SQLITE_VERSION}"
+            )
+            .into());
+        }
+        Ok(store)
+    }
+
+    fn upsert(conn: &Connection, snapshots: &PlayerSnapshots) -> Result<(), Error> {
+        conn.execute(
+            "INSERT INTO player_snapshots (region, uid, data, latest) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (region, uid) DO UPDATE SET
+                data = excluded.data,
+                latest = excluded.latest",
+            params![
+                snapshots.player.region.lower(),
+                snapshots.player.uid as i64,
+                serde_json::to_string(snapshots)?,
+                snapshots
+                    .data
+                    .last_key_value()
+                    .map(|(_, ships)| serde_json::to_string(ships))
+                    .transpose()?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// move the broken row aside, like [`quarantine_file`] of [`JsonStore`]
+    fn quarantine(
+        &self,
+        player: PartialPlayer,
+        data: &str,
+        err: impl Display,
+    ) -> Result<(), Error> {
+        let mut conn = self.conn.lock();
+        let tx = conn.transaction()?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let (region, uid) = (player.region.lower(), player.uid as i64);
+        tx.execute(
+            "INSERT INTO quarantine (region, uid, data, error, quarantined_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![region, uid, data, err.to_string(), now as i64],
+        )?;
+        tx.execute(
+            "DELETE FROM player_snapshots WHERE region = ?1 AND uid = ?2",
+            params![region, uid],
+        )?;
+        tx.commit()?;
+        let msg = format!("Quarantined broken snapshots row: {player:?}\n{err}");
+        error!("{msg}");
+        if let Some(webhook_tx) = crate::WEBHOOK_TX.get() {
+            let _ = webhook_tx.send(msg);
+        }
+        Ok(())
+    }
+}
+
+impl SnapshotStore for SqliteStore {
+    fn load(&self, player: PartialPlayer) -> Result<Option<PlayerSnapshots>, Error> {
+        let data = self
+            .conn
+            .lock()
+            .query_row(
+                "SELECT data FROM player_snapshots WHERE region = ?1 AND uid = ?2",
+                params![player.region.lower(), player.uid as i64],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        let Some(data) = data else {
+            return Ok(None);
+        };
+        match serde_json::from_str::<PlayerSnapshots>(&data) {
+            Ok(mut snapshots) => {
+                snapshots.player = player;
+                Ok(Some(snapshots))
+            }
+            // a broken row is treated as a new player, instead of failing every command forever
+            Err(err) => {
+                self.quarantine(player, &data, err)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn latest(&self, player: PartialPlayer) -> Result<Option<ShipStatsCollection>, Error> {
+        let conn = self.conn.lock();
+        conn.query_row(
+            "SELECT latest FROM player_snapshots WHERE region = ?1 AND uid = ?2",
+            params![player.region.lower(), player.uid as i64],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()?
+        .flatten()
+        .map(|data| serde_json::from_str(&data).map_err(Into::into))
+        .transpose()
+    }
+
+    fn save(&self, snapshots: &PlayerSnapshots) -> Result<(), Error> {
+        Self::upsert(&self.conn.lock(), snapshots)
+    }
+
+    fn delete(&self, player: PartialPlayer) -> Result<(), Error> {
+        self.conn.lock().execute(
+            "DELETE FROM player_snapshots WHERE region = ?1 AND uid = ?2",
+            params![player.region.lower(), player.uid as i64],
+        )?;
+        Ok(())
+    }
+
+    fn players(&self, region: Region) -> Result<Vec<PartialPlayer>, Error> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached("SELECT uid FROM player_snapshots WHERE region = ?1")?;
+        let players = stmt
+            .query_map(params![region.lower()], |row| row.get::<_, i64>(0))?
+            .map(|uid| {
+                uid.map(|uid| PartialPlayer {
+                    region,
+                    uid: uid as u64,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(players)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{JsonStore, SnapshotStore, SqliteStore};
+    use crate::structs::{PartialPlayer, PlayerSnapshots, Region, ShipStatsCollection};
+
+    #[tokio::test]
+    async fn sqlite_store_roundtrip() {
+        let store = SqliteStore::open(&PathBuf::from(":memory:")).unwrap();
+        let player = PartialPlayer {
+            region: Region::Asia,
+            uid: 2025455227,
+        };
+        let mut snapshots = PlayerSnapshots::init(player).await;
+        snapshots.data.insert(1, ShipStatsCollection::default());
+        snapshots.data.insert(2, ShipStatsCollection::default());
+        store.save(&snapshots).unwrap();
+
+        snapshots.data.remove(&1);
+        snapshots.session = Some((3, ShipStatsCollection::default()));
+        store.save(&snapshots).unwrap();
+        let loaded = store.load(player).unwrap().unwrap();
+        assert_eq!(loaded.data.keys().collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(loaded.session.map(|(date, _)| date), Some(3));
+        assert_eq!(store.players(Region::Asia).unwrap(), vec![player]);
+
+        store.delete(player).unwrap();
+        assert!(store.load(player).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn sqlite_store_quarantines_broken_row() {
+        let store = SqliteStore::open(&PathBuf::from(":memory:")).unwrap();
+        let player = PartialPlayer {
+            region: Region::Asia,
+            uid: 2025455227,
+        };
+        let mut snapshots = PlayerSnapshots::init(player).await;
+        snapshots.data.insert(1, ShipStatsCollection::default());
+        store.save(&snapshots).unwrap();
+        store
+            .conn
+            .lock()
+            .execute("UPDATE player_snapshots SET data = '{broken'", [])
+            .unwrap();
+
+        assert!(store.load(player).unwrap().is_none());
+        assert!(store.players(Region::Asia).unwrap().is_empty());
+        let quarantined: String = store
+            .conn
+            .lock()
+            .query_row("SELECT data FROM quarantine", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(quarantined, "{broken");
+    }
+
+    #[tokio::test]
+    async fn json_store_quarantines_broken_file() {
+        let root = std::env::temp_dir().join(format!("isac_json_store_{}", std::process::id()));
+        let store = JsonStore { root: root.clone() };
+        let player = PartialPlayer {
+            region: Region::Asia,
+            uid: 2025455227,
+        };
+        let mut snapshots = PlayerSnapshots::init(player).await;
+        snapshots.data.insert(1, ShipStatsCollection::default());
+        store.save(&snapshots).unwrap();
+        assert_eq!(store.load(player).unwrap().unwrap().data.len(), 1);
+
+        std::fs::write(store.get_path(player), b"{\"broken").unwrap();
+        assert!(store.load(player).unwrap().is_none());
+        // moved aside instead of being overwritten, and no longer listed as a player
+        assert!(!store.get_path(player).exists());
+        assert!(store.players(Region::Asia).unwrap().is_empty());
+        let quarantined = std::fs::read_dir(store.get_dir(Region::Asia))
+            .unwrap()
+            .count();
+        assert_eq!(quarantined, 1);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures::future::join_all;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::Url;
+
+use crate::{structs::Region, template_data::RENDER_POOL};
+
+use super::wws_api::WowsApi;
+
+/// the rolling health of the services ISAC depends on, probed by [`crate::tasks::health_monitor`]
+pub static UPSTREAM_HEALTH: Lazy<UpstreamHealth> = Lazy::new(UpstreamHealth::default);
+
+/// how many recent probes are kept for each service
+const WINDOW: usize = 10;
+
+/// a probe taking longer than this counts as a failure
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    Vortex(Region),
+    Api,
+    Clans,
+    Kokomi,
+    WowsNumbers,
+    Renderer,
+}
+
+impl Display for Service {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Service::Vortex(region) => write!(f, "Vortex {region}"),
+            Service::Api => write!(f, "WG API"),
+            Service::Clans => write!(f, "Clans API"),
+            Service::Kokomi => write!(f, "Kokomi"),
+            Service::WowsNumbers => write!(f, "wows-numbers"),
+            Service::Renderer => write!(f, "Renderer"),
+        }
+    }
+}
+
+impl Service {
+    pub const ALL: [Service; 8] = [
+        Service::Vortex(Region::Asia),
+        Service::Vortex(Region::Na),
+        Service::Vortex(Region::Eu),
+        Service::Api,
+        Service::Clans,
+        Service::Kokomi,
+        Service::WowsNumbers,
+        Service::Renderer,
+    ];
+
+    /// a cheap endpoint of the service
+    fn probe_url(&self) -> Url {
+        match self {
+            Service::Vortex(region) => {
+                region.vortex_url("/api/accounts/search/autocomplete/isac/?limit=1")
+            }
+            Service::Api => Region::Asia.api_url("/wows/encyclopedia/info/?fields=game_version"),
+            Service::Clans => {
+                Region::Asia.clan_url("/api/search/autocomplete/?search=isac&type=clans")
+            }
+            Service::Kokomi => Region::Asia.kokomi_url("/docs"),
+            // the body is big, only the headers are read
+            Service::WowsNumbers => {
+                Url::parse("https://api.wows-numbers.com/personal/rating/expected/json/").unwrap()
+            }
+            // the first worker, the pool is reported by the renderer supervisor
+            Service::Renderer => RENDER_POOL.workers[0].health_url(),
+        }
+    }
+
+    /// request the service once, without the retries of [`WowsApi::reqwest`]
+    ///
+    /// anything but a connection error, timeout or 5xx means it's up
+    async fn probe(&self, api: &WowsApi<'_>) -> Probe {
+        let mut request = api.client.get(self.probe_url()).timeout(PROBE_TIMEOUT);
+        if *self == Service::Api {
+            request = request.query(&[("application_id", api.token)]);
+        }
+        let start = Instant::now();
+        let error = match request.send().await {
+            Ok(res) if res.status().is_server_error() => Some(format!("HTTP {}", res.status())),
+            Ok(_) => None,
+            Err(err) => Some(err.without_url().to_string()),
+        };
+        Probe {
+            ok: error.is_none(),
+            latency: start.elapsed(),
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub ok: bool,
+    pub latency: Duration,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    /// not probed yet
+    Unknown,
+    Up,
+    /// up now, but failed recently
+    Degraded,
+    /// the latest 2 probes failed
+    Down,
+}
+
+impl Health {
+    /// judge the health by the probes, the latest one is at the back
+    fn of(probes: &VecDeque<Probe>) -> Self {
+        let Some(latest) = probes.back() else {
+            return Health::Unknown;
+        };
+        if probes.iter().rev().take(2).all(|p| !p.ok) {
+            Health::Down
+        } else if !latest.ok || probes.iter().any(|p| !p.ok) {
+            Health::Degraded
+        } else {
+            Health::Up
+        }
+    }
+
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Health::Unknown => "⚪",
+            Health::Up => "🟢",
+            Health::Degraded => "🟡",
+            Health::Down => "🔴",
+        }
+    }
+}
+
+impl Display for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Health::Unknown => "unknown",
+            Health::Up => "up",
+            Health::Degraded => "degraded",
+            Health::Down => "down",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// the health of a service for `/status`
+#[derive(Debug)]
+pub struct ServiceReport {
+    pub service: Service,
+    pub health: Health,
+    /// the latency of the latest probe
+    pub latency: Option<Duration>,
+    /// the ratio of the succeeded probes in the window
+    pub uptime: f64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct UpstreamHealth {
+    probes: Mutex<HashMap<Service, VecDeque<Probe>>>,
+    /// unix timestamp of the last round of probes
+    checked_at: AtomicU64,
+}
+
+impl UpstreamHealth {
+    /// probe all the services concurrently, return the ones whose health is changed
+    pub async fn probe_all(&self, api: &WowsApi<'_>) -> Vec<(Service, Health)> {
+        let probes = join_all(Service::ALL.iter().map(|service| service.probe(api))).await;
+        let changed = Service::ALL
+            .into_iter()
+            .zip(probes)
+            .filter_map(|(service, probe)| {
+                let before = self.health(service);
+                self.record(service, probe);
+                let after = self.health(service);
+                (before != after).then_some((service, after))
+            })
+            .collect();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.checked_at.store(now, Ordering::Relaxed);
+        changed
+    }
+
+    pub fn record(&self, service: Service, probe: Probe) {
+        let mut probes = self.probes.lock();
+        let window = probes.entry(service).or_default();
+        if window.len() == WINDOW {
+            window.pop_front();
+        }
+        window.push_back(probe);
+    }
+
+    pub fn health(&self, service: Service) -> Health {
+        self.probes
+            .lock()
+            .get(&service)
+            .map(Health::of)
+            .unwrap_or(Health::Unknown)
+    }
+
+    /// unix timestamp of the last round of probes, None if never probed
+    pub fn checked_at(&self) -> Option<u64> {
+        Some(self.checked_at.load(Ordering::Relaxed)).filter(|t| *t != 0)
+    }
+
+    pub fn report(&self) -> Vec<ServiceReport> {
+        let probes = self.probes.lock();
+        Service::ALL
+            .into_iter()
+            .map(|service| {
+                let window = probes.get(&service);
+                ServiceReport {
+                    service,
+                    health: window.map(Health::of).unwrap_or(Health::Unknown),
+                    latency: window.and_then(|w| w.back()).map(|p| p.latency),
+                    uptime: window
+                        .filter(|w| !w.is_empty())
+                        .map(|w| w.iter().filter(|p| p.ok).count() as f64 / w.len() as f64)
+                        .unwrap_or_default(),
+                    last_error: window.and_then(|w| w.iter().rev().find_map(|p| p.error.clone())),
+                }
+            })
+            .collect()
+    }
+
+    /// a note for the error messages if some services are known to be down
+    pub fn down_note(&self) -> Option<String> {
+        let down = Service::ALL
+            .into_iter()
+            .filter(|service| self.health(*service) == Health::Down)
+            .map(|service| format!("`{service}`"))
+            .collect::<Vec<_>>();
+        (!down.is_empty()).then(|| {
+            format!(
+                "\n{} {} seems down right now, not ISAC's fault, check `/status`",
+                Health::Down.emoji(),
+                down.join(", ")
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::VecDeque, time::Duration};
+
+    use super::{Health, Probe};
+
+    #[test]
+    fn health_of_probes() {
+        let probes = |results: &[bool]| {
+            results
+                .iter()
+                .map(|ok| Probe {
+                    ok: *ok,
+                    latency: Duration::ZERO,
+                    error: None,
+                })
+                .collect::<VecDeque<_>>()
+        };
+        assert_eq!(Health::of(&probes(&[])), Health::Unknown);
+        assert_eq!(Health::of(&probes(&[true, true])), Health::Up);
+        assert_eq!(Health::of(&probes(&[false, true])), Health::Degraded);
+        assert_eq!(Health::of(&probes(&[true, false])), Health::Degraded);
+        assert_eq!(Health::of(&probes(&[true, false, false])), Health::Down);
+        assert_eq!(Health::of(&probes(&[false])), Health::Down);
+    }
+}
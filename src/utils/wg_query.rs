@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use futures::future::try_join_all;
+use serde::de::DeserializeOwned;
+
+use crate::structs::{Region, api::WgApiRes};
+
+use super::{IsacError, wws_api::WowsApi};
+
+/// the official WG API endpoints we are using, `https://api.worldofwarships.asia/wows/{path}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WgEndpoint {
+    /// players' basic info
+    AccountInfo,
+    /// players' clan, `extra=clan` for the clan tag
+    ClanAccountInfo,
+    /// clan details, mainly for the rename history
+    ClanInfo,
+    /// player's clan battles seasons stats
+    ClanSeasonStats,
+}
+
+impl WgEndpoint {
+    pub fn path(&self) -> &'static str {
+        match self {
+            WgEndpoint::AccountInfo => "/wows/account/info/",
+            WgEndpoint::ClanAccountInfo => "/wows/clans/accountinfo/",
+            WgEndpoint::ClanInfo => "/wows/clans/info/",
+            WgEndpoint::ClanSeasonStats => "/wows/clans/seasonstats/",
+        }
+    }
+
+    /// the query parameter of the ids
+    pub fn id_field(&self) -> &'static str {
+        match self {
+            WgEndpoint::ClanInfo => "clan_id",
+            WgEndpoint::AccountInfo | WgEndpoint::ClanAccountInfo | WgEndpoint::ClanSeasonStats => {
+                "account_id"
+            }
+        }
+    }
+
+    /// the max ids in one request, WG rejects the request with more
+    pub fn max_ids(&self) -> usize {
+        100
+    }
+}
+
+/// a typed request builder of the official WG API, `application_id` is added automatically
+///
+/// ```ignore
+/// let clans = api
+///     .wg(region, WgEndpoint::ClanInfo)
+///     .ids(clan_ids)
+///     .fields(&["tag", "name"])
+///     .send::<ClanDetail>()
+///     .await?;
+/// ```
+pub struct WgQuery<'a> {
+    api: WowsApi<'a>,
+    region: Region,
+    endpoint: WgEndpoint,
+    ids: Vec<u64>,
+    fields: Vec<&'a str>,
+    extra: Vec<&'a str>,
+    language: &'a str,
+    params: Vec<(&'a str, String)>,
+}
+
+impl<'a> WgQuery<'a> {
+    pub fn new(api: WowsApi<'a>, region: Region, endpoint: WgEndpoint) -> Self {
+        Self {
+            api,
+            region,
+            endpoint,
+            ids: vec![],
+            fields: vec![],
+            extra: vec![],
+            language: "en",
+            params: vec![],
+        }
+    }
+
+    /// the ids to look up, they are split into batches of [`WgEndpoint::max_ids`]
+    pub fn ids(mut self, ids: impl IntoIterator<Item = u64>) -> Self {
+        self.ids.extend(ids);
+        self
+    }
+
+    pub fn id(self, id: u64) -> Self {
+        self.ids([id])
+    }
+
+    /// only return these fields, `-field` to exclude one
+    pub fn fields(mut self, fields: &[&'a str]) -> Self {
+        self.fields.extend(fields);
+        self
+    }
+
+    /// the extra fields not returned by default, e.g. `statistics.pvp_solo`
+    pub fn extra(mut self, extra: &[&'a str]) -> Self {
+        self.extra.extend(extra);
+        self
+    }
+
+    /// default: `en`
+    pub fn language(mut self, language: &'a str) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// any other query parameter of the endpoint
+    pub fn param(mut self, key: &'a str, value: impl ToString) -> Self {
+        self.params.push((key, value.to_string()));
+        self
+    }
+
+    /// the query of a batch of the ids
+    fn query(&self, ids: &[u64]) -> Vec<(&'a str, String)> {
+        let join = |values: &[&str]| values.join(",");
+        let mut query = vec![
+            ("application_id", self.api.token.to_string()),
+            ("language", self.language.to_string()),
+        ];
+        if !ids.is_empty() {
+            let ids: Vec<_> = ids.iter().map(u64::to_string).collect();
+            query.push((self.endpoint.id_field(), ids.join(",")));
+        }
+        if !self.fields.is_empty() {
+            query.push(("fields", join(&self.fields)));
+        }
+        if !self.extra.is_empty() {
+            query.push(("extra", join(&self.extra)));
+        }
+        query.extend(self.params.iter().cloned());
+        query
+    }
+
+    /// send the batches concurrently and merge their `data`, the value is None if the id is not found
+    pub async fn send<T: DeserializeOwned>(self) -> Result<HashMap<u64, Option<T>>, IsacError> {
+        let url = self.region.api_url(self.endpoint.path());
+        let batches: Vec<&[u64]> = match self.ids.is_empty() {
+            true => vec![&[]],
+            false => self.ids.chunks(self.endpoint.max_ids()).collect(),
+        };
+        let requests = batches.into_iter().map(|ids| {
+            let query = self.query(ids);
+            let url = url.clone();
+            async move {
+                self.api
+                    .reqwest(url, |b| b.query(&query))
+                    .await?
+                    .json::<WgApiRes<T>>()
+                    .await?
+                    .data()
+            }
+        });
+        Ok(try_join_all(requests)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    /// send the request of a single id, None if it's not found
+    pub async fn send_one<T: DeserializeOwned>(self) -> Result<Option<T>, IsacError> {
+        Ok(self.send::<T>().await?.into_values().next().flatten())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{WgEndpoint, WgQuery};
+    use crate::{Data, structs::Region, utils::wws_api::WowsApi};
+
+    #[test]
+    fn query_of_a_batch() {
+        let query = WgQuery::new(
+            WowsApi::from_data(Data::mock()),
+            Region::Asia,
+            WgEndpoint::ClanInfo,
+        )
+        .fields(&["tag", "name"])
+        .extra(&["members"])
+        .param("limit", 10)
+        .query(&[1, 2]);
+        assert_eq!(
+            query,
+            [
+                ("application_id", "mock".to_string()),
+                ("language", "en".to_string()),
+                ("clan_id", "1,2".to_string()),
+                ("fields", "tag,name".to_string()),
+                ("extra", "members".to_string()),
+                ("limit", "10".to_string()),
+            ]
+        );
+    }
+}
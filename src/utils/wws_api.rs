@@ -1,39 +1,57 @@
 use futures::future::try_join_all;
+use itertools::Itertools;
 use reqwest::{Client, IntoUrl, Response, Url};
 use serde::Deserialize;
-use std::fmt::{Debug, Display};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    time::Instant,
+};
 use strum::IntoEnumIterator;
 use ureq::typestate::WithoutBody;
 
 use crate::{
     Context, Data,
     structs::{
-        AutocompletePlayer, Clan, ClanDetail, ClanDetailAPIRes, ClanInfoAPIRes, ClanMemberAPIRes,
-        ClanTag, Mode, PartialClan, PartialPlayer, Player, PlayerClanAPIRes, PlayerClanBattle,
-        PlayerClanBattleAPIRes, Region, ShipId, ShipStatsCollection, ShipsPara, VortexPlayer,
-        VortexPlayerAPIRes, VortexShipAPIRes, VortexVehicleAPIRes, api,
+        AutocompletePlayer, Clan, ClanDetail, ClanInfoAPIRes, ClanMemberAPIRes, ClanTag, Mode,
+        PartialClan, PartialPlayer, Player, PlayerClanAPIRes, PlayerClanBattle, Region, ShipId,
+        ShipStatsCollection, ShipsPara, VortexPlayer, VortexPlayerAPIRes, VortexShipAPIRes,
+        VortexVehicleAPIRes, WgAccountInfo, WgClanAccountInfo, api,
     },
 };
 
-use super::{IsacError, IsacInfo};
+use super::{
+    IsacError, IsacInfo,
+    metrics::METRICS,
+    request_policy::{RequestPolicy, Upstream},
+    response_cache::RESPONSE_CACHE,
+    wg_query::{WgEndpoint, WgQuery},
+};
 
 #[derive(Clone, Copy)]
 pub struct WowsApi<'a> {
     pub client: &'a Client,
-    token: &'a str,
+    pub(super) token: &'a str,
     ctx_data: &'a Data,
 }
 
 impl<'a> WowsApi<'a> {
     pub fn new(ctx: &'a Context<'_>) -> WowsApi<'a> {
+        Self::from_data(ctx.data())
+    }
+
+    /// for the background tasks which don't have a [`Context`]
+    pub fn from_data(data: &'a Data) -> WowsApi<'a> {
         Self {
-            client: &ctx.data().client,
-            token: &ctx.data().wg_api_token,
-            ctx_data: ctx.data(),
+            client: &data.client,
+            token: &data.wg_api_token,
+            ctx_data: data,
         }
     }
 
     /// Helper function to handle errors, wrapped reqwest error into [`IsacInfo::APIError`]
+    ///
+    /// the request is rate limited and retried, see [`RequestPolicy`]
     pub async fn reqwest<F>(
         &self,
         url: impl IntoUrl + Display + Clone,
@@ -43,32 +61,93 @@ impl<'a> WowsApi<'a> {
         F: FnOnce(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
     {
         let request_builder = self.client.get(url.clone());
-        let request_builder = builder(request_builder);
-        request_builder
-            .send()
-            .await
-            .map_err(|err| Self::_err_wrap(&url, err))?
-            .error_for_status()
-            .map_err(|err| Self::_err_wrap(&url, err))
+        let request = builder(request_builder)
+            .build()
+            .map_err(|err| Self::_err_wrap(&url, err))?;
+        let policy = RequestPolicy::get();
+        let started = Instant::now();
+        let mut attempt = 0;
+        loop {
+            RequestPolicy::throttle(request.url()).await;
+            // GET requests have no body, so they can always be cloned
+            let res = self
+                .client
+                .execute(request.try_clone().expect("Failed to clone the request"))
+                .await
+                .and_then(|res| res.error_for_status());
+            match res {
+                Err(err)
+                    if attempt + 1 < policy.max_attempts
+                        && RequestPolicy::should_retry_reqwest(&err) =>
+                {
+                    tracing::debug!("retrying url: {url}, Err: {err}");
+                    tokio::time::sleep(policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                res => {
+                    Self::_record(request.url(), started, res.is_ok());
+                    return res.map_err(|err| Self::_err_wrap(&url, err));
+                }
+            }
+        }
     }
 
     /// Helper function to handle errors, wrapped ureq error into [`IsacInfo::APIError`]
+    ///
+    /// the request is rate limited and retried, see [`RequestPolicy`]
     pub async fn ureq<F>(
         &self,
         url: impl IntoUrl + Display + Clone,
         builder: F,
     ) -> Result<ureq::Body, IsacError>
     where
-        F: FnOnce(ureq::RequestBuilder<WithoutBody>) -> ureq::RequestBuilder<WithoutBody>,
+        F: Fn(ureq::RequestBuilder<WithoutBody>) -> ureq::RequestBuilder<WithoutBody>,
     {
-        let url = url.to_string();
-        let request_builder = ureq::get(&url);
-        let request_builder = builder(request_builder);
-        tokio::task::spawn_blocking(|| request_builder.call())
-            .await
-            .unwrap()
-            .map_err(|err| Self::_err_wrap(&url, err))
-            .map(|res| res.into_body())
+        let parsed_url = url
+            .clone()
+            .into_url()
+            .map_err(|err| Self::_err_wrap(&url, err))?;
+        let policy = RequestPolicy::get();
+        let started = Instant::now();
+        let mut attempt = 0;
+        loop {
+            RequestPolicy::throttle(&parsed_url).await;
+            let request_builder = builder(ureq::get(parsed_url.as_str()));
+            let res = tokio::task::spawn_blocking(|| request_builder.call())
+                .await
+                .unwrap();
+            match res {
+                Err(err)
+                    if attempt + 1 < policy.max_attempts
+                        && RequestPolicy::should_retry_ureq(&err) =>
+                {
+                    tracing::debug!("retrying url: {url}, Err: {err}");
+                    tokio::time::sleep(policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                res => {
+                    Self::_record(&parsed_url, started, res.is_ok());
+                    return res
+                        .map_err(|err| Self::_err_wrap(&url, err))
+                        .map(|res| res.into_body());
+                }
+            }
+        }
+    }
+
+    /// the metrics of a request, see [`METRICS`]
+    fn _record(url: &Url, started: Instant, ok: bool) {
+        let upstream = Upstream::of(url).map_or("other", <&str>::from);
+        let result = if ok { "ok" } else { "error" };
+        METRICS.inc(
+            "isac_upstream_requests_total",
+            &[("upstream", upstream), ("result", result)],
+        );
+        METRICS.observe(
+            "isac_upstream_request_duration_seconds",
+            &[("upstream", upstream)],
+            started.elapsed(),
+        );
     }
 
     /// log error and return [`IsacError`]
@@ -82,6 +161,17 @@ impl<'a> WowsApi<'a> {
         &self,
         region: Region,
         uid: u64,
+    ) -> Result<Player, IsacError> {
+        RESPONSE_CACHE
+            .player
+            .get_or_fetch(region, uid, self.fetch_player_personal_data(region, uid))
+            .await
+    }
+
+    async fn fetch_player_personal_data(
+        &self,
+        region: Region,
+        uid: u64,
     ) -> Result<Player, IsacError> {
         let url = region.vortex_url(format!("/api/accounts/{uid}"));
 
@@ -197,49 +287,153 @@ impl<'a> WowsApi<'a> {
         &self,
         region: &Region,
         player_uid: u64,
+    ) -> Result<Option<PartialClan>, IsacError> {
+        RESPONSE_CACHE
+            .player_clan
+            .get_or_fetch(
+                *region,
+                player_uid,
+                self.fetch_player_clan(region, player_uid),
+            )
+            .await
+    }
+
+    async fn fetch_player_clan(
+        &self,
+        region: &Region,
+        player_uid: u64,
     ) -> Result<Option<PartialClan>, IsacError> {
         let url = region.vortex_url(format!("/api/accounts/{player_uid}/clans/"));
         let res = self
             .reqwest(url, |b| b)
             .await?
-            // return None if clan API is fucked
             .json::<PlayerClanAPIRes>()
-            .await
-            .unwrap();
+            .await?;
 
         res.into_partial_clan(*region)
     }
 
+    /// turn many players into [`Player`] with the official api, 100 players per request
+    ///
+    /// hidden, not found and no battle players are missing in the map, and the dogtags are empty
+    pub async fn full_players(
+        &self,
+        players: &[PartialPlayer],
+    ) -> Result<HashMap<PartialPlayer, Player>, IsacError> {
+        let requests =
+            Self::_group_by_region(players)
+                .into_iter()
+                .map(|(region, uids)| async move {
+                    let res = self
+                        .wg(region, WgEndpoint::AccountInfo)
+                        .ids(uids)
+                        .fields(&["nickname", "hidden_profile", "leveling_points", "karma"])
+                        .send::<WgAccountInfo>()
+                        .await?;
+                    Ok::<_, IsacError>((region, res))
+                });
+        let responses = try_join_all(requests).await?;
+
+        let banner = self.ctx_data.banner.read().await;
+        let players = responses
+            .into_iter()
+            .flat_map(|(region, res)| res.into_iter().map(move |(uid, info)| (region, uid, info)))
+            .filter_map(|(region, uid, info)| {
+                let info = info.filter(|i| !i.hidden_profile && i.leveling_points != 0)?;
+                let partial_player = PartialPlayer { region, uid };
+                let banner = banner.get(&uid).map(|r| r.url);
+                let player = Player {
+                    partial_player,
+                    uid,
+                    ign: info.nickname,
+                    region,
+                    karma: info.karma.unwrap_or_default(),
+                    dogtag: String::new(),
+                    dogtag_bg: String::new(),
+                    premium: banner.is_some(),
+                    banner: banner.unwrap_or_default(),
+                };
+                Some((partial_player, player))
+            })
+            .collect();
+        Ok(players)
+    }
+
+    /// the clan tags of many players with the official api, 100 players per request
+    ///
+    /// players without a clan are missing in the map
+    pub async fn player_clan_tags(
+        &self,
+        players: &[PartialPlayer],
+    ) -> Result<HashMap<PartialPlayer, ClanTag>, IsacError> {
+        let requests =
+            Self::_group_by_region(players)
+                .into_iter()
+                .map(|(region, uids)| async move {
+                    let res = self
+                        .wg(region, WgEndpoint::ClanAccountInfo)
+                        .ids(uids)
+                        .fields(&["clan_id", "clan.tag"])
+                        .extra(&["clan"])
+                        .send::<WgClanAccountInfo>()
+                        .await?;
+                    Ok::<_, IsacError>((region, res))
+                });
+        Ok(try_join_all(requests)
+            .await?
+            .into_iter()
+            .flat_map(|(region, res)| {
+                res.into_iter().filter_map(move |(uid, info)| {
+                    let tag = info?.clan?.tag;
+                    Some((PartialPlayer { region, uid }, tag))
+                })
+            })
+            .collect())
+    }
+
+    /// (region, uids), the official api can only look up the players in the same region at once
+    fn _group_by_region(players: &[PartialPlayer]) -> HashMap<Region, Vec<u64>> {
+        players
+            .iter()
+            .unique()
+            .map(|p| (p.region, p.uid))
+            .into_group_map()
+    }
+
     // wows api doesn't support basic_exp yet, so using vortex still
-    // TODO: make a builder pattern for wows api
-    /// player's all ships stats
-    // pub async fn statistics_of_player_ships(
-    //     &self,
-    //     region: Region,
-    //     uid: u64,
-    //     ship_id: Option<u64>,
-    // ) -> Result<(), IsacError> {
-    //     let url = region.api_url("/wows/ships/stats/")?;
-    //     let mut query = vec![
-    //         ("application_id", self.token.to_string()),
-    //         ("language", "en".to_string()),
-    //         ("account_id", uid.to_string()),
-    //         ("extra", "pvp_div2, pvp_div3, pvp_solo".to_string()),
-    //     ];
-    //     if let Some(ship_id) = ship_id {
-    //         query.push(("ship_id", ship_id.to_string()));
-    //     }
-    //     let res = self.client.get(url).query(&query).send();
-    // }
     /// if `ship_id` is None, it will return all ships statistics
+    ///
+    /// one request per mode, the modes of [`Mode::is_extra`] are included only with `extra_modes`
     pub async fn statistics_of_player_ships(
         &self,
         region: Region,
         uid: u64,
         ship_id: Option<ShipId>,
+        extra_modes: bool,
     ) -> Result<ShipStatsCollection, IsacError> {
+        let fetch = self.fetch_statistics_of_player_ships(region, uid, ship_id, extra_modes);
+        match (ship_id, extra_modes) {
+            (Some(_), _) => fetch.await,
+            (None, false) => RESPONSE_CACHE.ships.get_or_fetch(region, uid, fetch).await,
+            (None, true) => {
+                RESPONSE_CACHE
+                    .ships_extra
+                    .get_or_fetch(region, uid, fetch)
+                    .await
+            }
+        }
+    }
+
+    async fn fetch_statistics_of_player_ships(
+        &self,
+        region: Region,
+        uid: u64,
+        ship_id: Option<ShipId>,
+        extra_modes: bool,
+    ) -> Result<ShipStatsCollection, IsacError> {
+        let modes = Mode::iter().filter(|mode| extra_modes || !mode.is_extra());
         let urls: Vec<Url> = if let Some(ship_id) = ship_id {
-            Mode::iter()
+            modes
                 .map(|mode| {
                     region.vortex_url(format!(
                         "/api/accounts/{uid}/ships/{ship_id}/{}/",
@@ -248,7 +442,7 @@ impl<'a> WowsApi<'a> {
                 })
                 .collect()
         } else {
-            Mode::iter()
+            modes
                 .map(|mode| {
                     region.vortex_url(format!("/api/accounts/{uid}/ships/{}/", mode.api_name()))
                 })
@@ -278,13 +472,19 @@ impl<'a> WowsApi<'a> {
     }
     /// clan details from vortex
     pub async fn clan_stats(&self, region: Region, clan_id: u64) -> Result<Clan, IsacError> {
+        RESPONSE_CACHE
+            .clan
+            .get_or_fetch(region, clan_id, self.fetch_clan_stats(region, clan_id))
+            .await
+    }
+
+    async fn fetch_clan_stats(&self, region: Region, clan_id: u64) -> Result<Clan, IsacError> {
         let url = region.clan_url(format!("/api/clanbase/{clan_id}/claninfo/"));
         let mut clan: Clan = self
             .reqwest(url, |b| b)
             .await?
             .json::<ClanInfoAPIRes>()
-            .await
-            .unwrap()
+            .await?
             .into();
         // insert the region here
         clan.info.region = region;
@@ -315,49 +515,28 @@ impl<'a> WowsApi<'a> {
         Ok(clan)
     }
 
+    /// a request to the official api, see [`WgQuery`]
+    pub fn wg(&self, region: Region, endpoint: WgEndpoint) -> WgQuery<'a> {
+        WgQuery::new(*self, region, endpoint)
+    }
+
     /// clan details from official api
     pub async fn clan_details(
         &self,
         region: Region,
         clan_id: u64,
     ) -> Result<ClanDetail, IsacError> {
-        let url = region.api_url(format!("/wows/clans/info/{clan_id}"));
-        let query = vec![
-            ("application_id", self.token.to_string()),
-            ("language", "en".to_string()),
-            ("clan_id", clan_id.to_string()),
-            // ("extra", "members".to_string()),
-        ];
-
-        // // TEMP code for DEBUG here
-
-        // let res = self
-        //     .client
-        //     .get(url)
-        //     .query(&query)
-        //     .send()
-        //     .await
-        //     .and_then(|res| res.error_for_status())
-        //     .map_err(Self::_err_wrap)?;
-        // let res_status = res.status();
-        // let res_text = res.text().await?;
-
-        // match serde_json::from_str::<ClanDetailAPIRes>(&res_text) {
-        //     Ok(clan_res) => clan_res.data(),
-        //     Err(err) => {
-        //         println!("Err code: {}", res_status);
-        //         println!("Response: {:?}", res_text);
-        //         panic!("{:?}", err)
-        //     }
-        // }
-
-        let clan_res: ClanDetailAPIRes = self
-            .reqwest(url, |b| b.query(&query))
+        self.wg(region, WgEndpoint::ClanInfo)
+            .id(clan_id)
+            .send_one::<ClanDetail>()
             .await?
-            .json::<ClanDetailAPIRes>()
-            .await
-            .unwrap();
-        clan_res.data()
+            .ok_or(
+                IsacInfo::ClanNotFound {
+                    clan: clan_id.to_string(),
+                    region,
+                }
+                .into(),
+            )
     }
 
     /// player's CB seasons stats from official api
@@ -366,26 +545,59 @@ impl<'a> WowsApi<'a> {
         region: Region,
         uid: u64,
     ) -> Result<PlayerClanBattle, IsacError> {
-        let url = region.api_url("/wows/clans/seasonstats/");
-        let query = vec![
-            ("application_id", self.token.to_string()),
-            ("language", "en".to_string()),
-            ("account_id", uid.to_string()),
-        ];
-        let mut res: PlayerClanBattle = self
-            .reqwest(url, |b| b.query(&query))
-            .await?
-            .json::<PlayerClanBattleAPIRes>()
+        let mut res = self
+            .wg(region, WgEndpoint::ClanSeasonStats)
+            .id(uid)
+            .send_one::<PlayerClanBattle>()
             .await?
-            .try_into()?;
+            .unwrap_or_default();
         // filter out some ancient clan battle seasons
         res.seasons.retain(|s| !matches!(s.season_id, 101 | 102));
         Ok(res)
     }
 
+    /// the ship's leaderboard from Kokomi, sorted by base exp, return the `data` of the response
+    pub async fn kokomi_ship_ranking(
+        &self,
+        region: Region,
+        ship_id: ShipId,
+    ) -> Result<serde_json::Value, IsacError> {
+        let kokomi_token =
+            self.ctx_data
+                .kokomi_api_token
+                .as_deref()
+                .ok_or(IsacInfo::GeneralError {
+                    msg: "Kokomi API token not set in env".to_string(),
+                })?;
+        let mut res_json = self
+            .reqwest(
+                region.kokomi_url(format!("/api/external/ship/ranking/{ship_id}/")),
+                |b| {
+                    b.header("Access-Token", kokomi_token)
+                        .query(&[("size", "100"), ("dogtag", "0")])
+                },
+            )
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+
+        if res_json.get("status") != Some(&serde_json::Value::String("ok".to_string())) {
+            Err(IsacInfo::GeneralError {
+                msg: format!(
+                    "Kokomi API error: {}",
+                    res_json
+                        .get("message")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Unknown error")
+                ),
+            })?;
+        }
+        Ok(res_json["data"].take())
+    }
+
     pub async fn encyclopedia_vehicles(&self) -> Result<ShipsPara, IsacError> {
         self.reqwest(
-            "https://vortex.worldofwarships.com/api/encyclopedia/en/vehicles/",
+            Region::Na.vortex_url("/api/encyclopedia/en/vehicles/"),
             |b| b,
         )
         .await?
@@ -453,3 +665,158 @@ impl ClanSearchResClan {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::WowsApi;
+    use crate::{
+        Data,
+        structs::{Mode, PartialPlayer, Region, ShipId},
+        utils::{IsacError, IsacInfo, mock_upstream},
+    };
+
+    /// the fixtures are in `tests/fixtures`, see [`mock_upstream`]
+    ///
+    /// every test uses its own ids, the responses are cached across the tests
+    fn api() -> WowsApi<'static> {
+        mock_upstream::start();
+        WowsApi::from_data(Data::mock())
+    }
+
+    #[tokio::test]
+    async fn player_personal_data() {
+        let player = api()
+            .player_personal_data(Region::Asia, 1001)
+            .await
+            .unwrap();
+        assert_eq!((player.ign.as_str(), player.karma), ("fixture_player", 77));
+    }
+
+    #[tokio::test]
+    async fn hidden_player() {
+        let err = api()
+            .player_personal_data(Region::Asia, 1002)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, IsacError::Info(IsacInfo::PlayerHidden { ign }) if ign == "hidden_player")
+        );
+        let err = api()
+            .statistics_of_player_ships(Region::Asia, 1002, None, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            IsacError::Info(IsacInfo::PlayerHidden { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn player_clan() {
+        let clan = api()
+            .player_clan(&Region::Asia, 1001)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((&*clan.tag, clan.id), ("FIX", 2000000001));
+        // never joined a clan
+        assert!(
+            api()
+                .player_clan(&Region::Asia, 1003)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn clan_stats() {
+        let clan = api().clan_stats(Region::Asia, 2000000001).await.unwrap();
+        assert_eq!(clan.info.region, Region::Asia);
+        assert_eq!(clan.stats.ratings.len(), 1);
+
+        let empty = api().clan_stats(Region::Asia, 2000000002).await.unwrap();
+        assert_eq!(empty.info.members_count, 0);
+        assert!(empty.stats.ratings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn ship_stats_of_all_modes() {
+        let ships = api()
+            .statistics_of_player_ships(Region::Asia, 1001, None, true)
+            .await
+            .unwrap();
+        let modes = &ships.0[&ShipId(3530504176)].0;
+        assert_eq!(modes.keys().collect::<Vec<_>>(), [&Mode::Pvp]);
+
+        let ship = api()
+            .statistics_of_player_ships(Region::Asia, 1001, Some(ShipId(3530504176)), false)
+            .await
+            .unwrap();
+        assert_eq!(ship.0.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn bad_status() {
+        // 503, after the retries
+        let err = api()
+            .player_personal_data(Region::Asia, 1005)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, IsacError::Info(IsacInfo::APIError { .. })));
+        // 200 with an error body
+        let err = api()
+            .player_personal_data(Region::Asia, 1004)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, IsacError::Info(IsacInfo::APIError { msg }) if msg == "SOURCE_NOT_AVAILABLE")
+        );
+    }
+
+    #[tokio::test]
+    async fn clan_battle_season_stats() {
+        let res = api()
+            .clan_battle_season_stats(Region::Asia, 1001)
+            .await
+            .unwrap();
+        // the ancient seasons are filtered out
+        assert_eq!(
+            res.seasons.iter().map(|s| s.season_id).collect::<Vec<_>>(),
+            [22]
+        );
+    }
+
+    #[tokio::test]
+    async fn batched_players() {
+        let players: Vec<_> = (2001..=2004)
+            .map(|uid| PartialPlayer {
+                region: Region::Asia,
+                uid,
+            })
+            .collect();
+        let full_players = api().full_players(&players).await.unwrap();
+        assert_eq!(full_players.len(), 1);
+        assert_eq!(full_players[&players[0]].ign, "batch_player");
+
+        let clan_tags = api().player_clan_tags(&players).await.unwrap();
+        assert_eq!(clan_tags.len(), 1);
+        assert_eq!(&*clan_tags[&players[0]], "FIX");
+    }
+
+    #[tokio::test]
+    async fn kokomi_error_body() {
+        let err = api()
+            .kokomi_ship_ranking(Region::Asia, ShipId(4000000001))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, IsacError::Info(IsacInfo::GeneralError { msg }) if msg == "Kokomi API error: Ship not supported")
+        );
+        let data = api()
+            .kokomi_ship_ranking(Region::Asia, ShipId(4000000002))
+            .await
+            .unwrap();
+        assert_eq!(data.as_array().map(Vec::len), Some(0));
+    }
+}